@@ -20,13 +20,12 @@ All text above must be included in any redistribution
 
 #![no_std]
 
-use crate::adafruit_gfx_h::{ bv, AdafruitGFX, Drawable, BLACK, WHITE };
-use crate::glcdfont::{ self, FONT };
+use crate::adafruit_gfx_h::{ bv, AdafruitGFX, Drawable, RasterOp, Rect, BLACK, WHITE };
 
-impl AdafruitGFX {
+impl<const N: usize> AdafruitGFX<N> {
     pub fn new(w: i16, h: i16) -> Self {
-    let buffer: [u8; 1024] = [0; 1024];        //  (128x64)/8   Uncomment this line if you have 64 bit, and comment the below line. 
-    //let buffer: [u8; 512] = [0; 512];          // (128x32)/8 
+        assert!((w as usize) * (h as usize).div_ceil(8) <= N, "buffer too small for w x h");
+        let buffer = [0; N];
         Self {
             raw_width: w, // this is the 'raw' display w/h - never changes
             raw_height: h,
@@ -39,71 +38,82 @@ impl AdafruitGFX {
             textsize: 1,
             rotation: 0,
             wrap: true,
+            zoom: false,
             buffer,
         }
     }
-}
-impl Drawable for AdafruitGFX {
-    fn draw_circle(&mut self, x0: i16, y0: i16, r: i16, color: u8) {
-        let mut f: i32 = 1 - (r as i32);
-        let mut ddF_x = 1;
-        let mut ddF_y: i32 = -2 * (r as i32);
-        let mut x = 0;
-        let mut y = r;
-
-        self.draw_pixel(x0, y0 + r, color);
-        self.draw_pixel(x0, y0 - r, color);
-        self.draw_pixel(x0 + r, y0, color);
-        self.draw_pixel(x0 - r, y0, color);
-
-        while x < y {
-            if f >= 0 {
-                y -= 1;
-                ddF_y += 2;
-                f += ddF_y;
+
+    /// Match the controller's zoom-in mode: drawing is limited to the top half of the raw height
+    pub fn set_zoom(&mut self, zoom: bool) {
+        self.zoom = zoom;
+        self.set_rotation(self.rotation);
+    }
+
+    /// Raw height of the part of the buffer that is visible on the panel
+    pub fn visible_raw_height(&self) -> i16 {
+        if self.zoom { self.raw_height / 2 } else { self.raw_height }
+    }
+
+    /// Buffer (raw) coordinates of the pixel (x, y), following the rotation
+    pub fn raw_point(&self, x: i16, y: i16) -> (i16, i16) {
+        match self.rotation {
+            1 => (self.raw_width - y - 1, x),
+            2 => (self.raw_width - x - 1, self.visible_raw_height() - y - 1),
+            3 => (y, self.visible_raw_height() - x - 1),
+            _ => (x, y),
+        }
+    }
+
+    /// Colour of the pixel (x, y), BLACK outside the display
+    pub fn get_pixel(&self, x: i16, y: i16) -> u8 {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return BLACK;
+        }
+        let (x, y) = self.raw_point(x, y);
+        if self.buffer[(x + (y / 8) * self.raw_width) as usize] & bv((y % 8) as u8) != 0 { WHITE } else { BLACK }
+    }
+
+    /// Combine every pixel of `src` with this buffer at (x, y), following both rotations
+    pub fn blit<const M: usize>(&mut self, src: &AdafruitGFX<M>, x: i16, y: i16, op: RasterOp) {
+        for j in 0..src.height {
+            for i in 0..src.width {
+                let s = src.get_pixel(i, j);
+                let color = match op {
+                    RasterOp::Copy => s,
+                    RasterOp::Or => s | self.get_pixel(x + i, y + j),
+                    RasterOp::And => s & self.get_pixel(x + i, y + j),
+                    RasterOp::Xor => s ^ self.get_pixel(x + i, y + j),
+                };
+                self.draw_pixel(x + i, y + j, color);
             }
-            x += 1;
-            ddF_x += 2;
-            f += ddF_x;
-
-            self.draw_pixel(x0 + x, y0 + y, color);
-            self.draw_pixel(x0 - x, y0 + y, color);
-            self.draw_pixel(x0 + x, y0 - y, color);
-            self.draw_pixel(x0 - x, y0 - y, color);
-            self.draw_pixel(x0 + y, y0 + x, color);
-            self.draw_pixel(x0 - y, y0 + x, color);
-            self.draw_pixel(x0 + y, y0 - x, color);
-            self.draw_pixel(x0 - y, y0 - x, color);
         }
     }
 
+    /// The part of `r` on the display, in buffer (raw) coordinates. None if nothing is visible
+    pub fn raw_rect(&self, r: Rect) -> Option<Rect> {
+        let x0 = core::cmp::max(r.x, 0);
+        let y0 = core::cmp::max(r.y, 0);
+        let x1 = core::cmp::min(r.x + r.w, self.width) - 1;
+        let y1 = core::cmp::min(r.y + r.h, self.height) - 1;
+        if x1 < x0 || y1 < y0 {
+            return None;
+        }
+
+        let (ax, ay) = self.raw_point(x0, y0);
+        let (bx, by) = self.raw_point(x1, y1);
+        let (left, top) = (core::cmp::min(ax, bx), core::cmp::min(ay, by));
+        let (right, bottom) = (core::cmp::max(ax, bx), core::cmp::max(ay, by));
+        Some(Rect::new(left, top, right - left + 1, bottom - top + 1))
+    }
+}
+impl<const N: usize> Drawable for AdafruitGFX<N> {
     fn draw_pixel(&mut self, mut x: i16, mut  y: i16, color: u8) {
-        if x >= self.get_width() || y >= self.get_height() {
+        if x < 0 || y < 0 || x >= self.get_width() || y >= self.get_height() {
             return;
         }
 
         // Check rotation, move pixel around if necessary
-        match self.get_rotation() {
-            1 => {
-                // Swap x and y
-                let temp = x;
-                x = y;
-                y = temp;
-                x = self.raw_width - x - 1;
-            }
-            2 => {
-                x = self.raw_width - x - 1;
-                y = self.raw_height - y - 1;
-            }
-            3 => {
-                // Swap x and y
-                let temp = x;
-                x = y;
-                y = temp;
-                y = self.raw_height - y - 1;
-            }
-            _ => {}
-        }
+        (x, y) = self.raw_point(x, y);
 
         // x is which column
         if color == WHITE {
@@ -113,288 +123,22 @@ impl Drawable for AdafruitGFX {
         }
     }
 
-    // Draw a circle helper
-    fn draw_circle_helper(&mut self, x0: i16, y0: i16, r: i16, cornername: u8, color: u8) {
-        let mut f: i32 = 1 - (r as i32);
-        let mut ddF_x = 1;
-        let mut ddF_y: i32 = -2 * (r as i32);
-        let mut x = 0;
-        let mut y = r;
-
-        while x < y {
-            if f >= 0 {
-                y -= 1;
-                ddF_y += 2;
-                f += ddF_y;
-            }
-            x += 1;
-            ddF_x += 2;
-            f += ddF_x;
-
-            if (cornername & 0x4) != 0 {
-                self.draw_pixel(x0 + x, y0 + y, color);
-                self.draw_pixel(x0 + y, y0 + x, color);
-            }
-
-            if (cornername & 0x2) != 0 {
-                self.draw_pixel(x0 + x, y0 - y, color);
-                self.draw_pixel(x0 + y, y0 - x, color);
-            }
-
-            if (cornername & 0x8) != 0 {
-                self.draw_pixel(x0 - y, y0 + x, color);
-                self.draw_pixel(x0 - x, y0 + y, color);
-            }
-
-            if (cornername & 0x1) != 0 {
-                self.draw_pixel(x0 - y, y0 - x, color);
-                self.draw_pixel(x0 - x, y0 - y, color);
-            }
-        }
-    }
-
-    // Fill a circle
-    fn fill_circle(&mut self, x0: i16, y0: i16, r: i16, color: u8) {
-        self.draw_fast_v_line(x0, y0 - r, 2 * r + 1, color);
-        self.fill_circle_helper(x0, y0, r, 3, 0, color);
-    }
-
-    // Fill circle helper
-    fn fill_circle_helper(
-        &mut self,
-        x0: i16,
-        y0: i16,
-        r: i16,
-        cornername: u8,
-        delta: i16,
-        color: u8
-    ) {
-        let mut f: i32 = 1 - (r as i32);
-        let mut ddF_x = 1;
-        let mut ddF_y: i32 = -2 * (r as i32);
-        let mut x = 0;
-        let mut y = r;
-
-        while x < y {
-            if f >= 0 {
-                y -= 1;
-                ddF_y += 2;
-                f += ddF_y;
-            }
-            x += 1;
-            ddF_x += 2;
-            f += ddF_x;
-
-            if (cornername & 0x1) != 0 {
-                self.draw_fast_v_line(x0 + x, y0 - y, 2 * y + 1 + delta, color);
-                self.draw_fast_v_line(x0 + y, y0 - x, 2 * x + 1 + delta, color);
-            }
 
-            if (cornername & 0x2) != 0 {
-                self.draw_fast_v_line(x0 - x, y0 - y, 2 * y + 1 + delta, color);
-                self.draw_fast_v_line(x0 - y, y0 - x, 2 * x + 1 + delta, color);
-            }
-        }
-    }
 
-    // Bresenham's algorithm for drawing a line
-    fn draw_line(&mut self, x0: i16, y0: i16, x1: i16, y1: i16, color: u8) {
-        let dx = ((x1 as i32) - (x0 as i32)).abs();
-        let dy = ((y1 as i32) - (y0 as i32)).abs();
-        let steep = dy > dx;
 
-        let (x0, y0, x1, y1) = if steep {
-            (y0, x0, y1, x1) // Swap x and y
-        } else {
-            (x0, y0, x1, y1)
-        };
 
-        let (x0, x1) = if x0 > x1 { (x1, x0) } else { (x0, x1) };
 
-        let dx: i32 = (x1 as i32) - (x0 as i32);
-        let dy = ((y1 as i32) - (y0 as i32)).abs();
-        let mut err: i32 = dx / 2;
-        let ystep: i32 = if y0 < y1 { 1 } else { -1 };
 
-        let mut y: i32 = y0 as i32;
 
-        for x in x0..=x1 {
-            if steep {
-                self.draw_pixel(y as i16, x, color);
-            } else {
-                self.draw_pixel(x, y as i16, color);
-            }
 
-            err -= dy;
-            if err < 0 {
-                y += ystep;
-                err += dx;
-            }
-        }
-    }
 
-    // Draw a vertical line
-    fn draw_fast_v_line(&mut self, x: i16, y: i16, h: i16, color: u8) {
-        self.draw_line(x, y, x, y + h - 1, color);
-    }
 
-    // Fill a rectangle
-    fn fill_rect(&mut self, x: i16, y: i16, w: i16, h: i16, color: u8) {
-        for i in x..x + w {
-            self.draw_fast_v_line(i, y, h, color);
-        }
-    }
 
-    // Draw a rectangle
-    fn draw_rect(&mut self, x: i16, y: i16, w: i16, h: i16, color: u8) {
-        self.draw_fast_h_line(x, y, w, color);
-        self.draw_fast_h_line(x, y + h - 1, w, color);
-        self.draw_fast_v_line(x, y, h, color);
-        self.draw_fast_v_line(x + w - 1, y, h, color);
-    }
 
-    // Draw a horizontal line
-    fn draw_fast_h_line(&mut self, x: i16, y: i16, w: i16, color: u8) {
-        self.draw_line(x, y, x + w - 1, y, color);
-    }
 
-    // Fill the entire display
-    fn fill_screen(&mut self, color: u8) {
-        self.fill_rect(0, 0, self.width as i16, self.height as i16, color);
-    }
 
-    // Draw a rounded rectangle
-    fn draw_round_rect(&mut self, x: i16, y: i16, w: i16, h: i16, r: i16, color: u8) {
-        self.draw_fast_h_line(x + r, y, w - 2 * r, color); // Top
-        self.draw_fast_h_line(x + r, y + h - 1, w - 2 * r, color); // Bottom
-        self.draw_fast_v_line(x, y + r, h - 2 * r, color); // Left
-        self.draw_fast_v_line(x + w - 1, y + r, h - 2 * r, color); // Right
-        self.draw_circle_helper(x + r, y + r, r, 1, color); // Top-left
-        self.draw_circle_helper(x + w - r - 1, y + r, r, 2, color); // Top-right
-        self.draw_circle_helper(x + w - r - 1, y + h - r - 1, r, 4, color); // Bottom-right
-        self.draw_circle_helper(x + r, y + h - r - 1, r, 8, color); // Bottom-left
-    }
 
-    // Fill a rounded rectangle
-    fn fill_round_rect(&mut self, x: i16, y: i16, w: i16, h: i16, r: i16, color: u8) {
-        self.fill_rect(x + r, y, w - 2 * r, h, color);
-        self.fill_circle_helper(x + w - r - 1, y + r, r, 1, h - 2 * r - 1, color);
-        self.fill_circle_helper(x + r, y + r, r, 2, h - 2 * r - 1, color);
-    }
 
-    // Draw a triangle
-    fn draw_triangle(
-        &mut self,
-        x0: i16,
-        y0: i16,
-        x1: i16,
-        y1: i16,
-        x2: i16,
-        y2: i16,
-        color: u8
-    ) {
-        self.draw_line(x0, y0, x1, y1, color);
-        self.draw_line(x1, y1, x2, y2, color);
-        self.draw_line(x2, y2, x0, y0, color);
-    }
-
-    // Fill a triangle
-    fn fill_triangle(
-        &mut self,
-        mut x0: i16,
-        mut y0: i16,
-        mut x1: i16,
-        mut y1: i16,
-        mut x2: i16,
-        mut y2: i16,
-        mut color: u8
-    ) {
-        let mut a: i16 = 0;
-        let mut b: i16 = 0;
-        let mut y: i32 = 0;
-        let mut last: i16 = 0;
-
-        // Sort coordinates by Y order (y2 >= y1 >= y0)
-        if y0 > y1 {
-            (&mut y0, &mut y1);
-            self.swap(&mut x0, &mut x1);
-        }
-        if y1 > y2 {
-            self.swap(&mut y2, &mut y1);
-            self.swap(&mut x2, &mut x1);
-        }
-        if y0 > y1 {
-            self.swap(&mut y0, &mut y1);
-            self.swap(&mut x0, &mut x1);
-        }
-
-        if y0 == y2 {
-            a = x0;
-            b = x0;
-            if x1 < a {
-                a = x1;
-            } else if x1 > b {
-                b = x1;
-            }
-            if x2 < a {
-                a = x2;
-            } else if x2 > b {
-                b = x2;
-            }
-            self.draw_fast_h_line(a, y0, b - a + 1, color);
-            return;
-        }
-
-        let dx01 = x1 - x0;
-        let dy01 = y1 - y0;
-        let dx02 = x2 - x0;
-        let dy02 = y2 - y0;
-        let dx12 = x2 - x1;
-        let dy12 = y2 - y1;
-        let mut sa = 0;
-        let mut sb = 0;
-
-        if y1 == y2 {
-            last = y1;
-        } else {
-            last = y1 - 1;
-        }
-
-        for y in y0..=last {
-            a = x0 + sa / dy01;
-            b = x0 + sb / dy02;
-            sa += dx01;
-            sb += dx02;
-            if a > b {
-                self.swap(&mut a, &mut b);
-            }
-            self.draw_fast_h_line(a, y, b - a + 1, color);
-        }
-
-        sa = dx12 * (((y as i32) - (y1 as i32)) as i16);
-        sb = dx02 * (((y as i32) - (y0 as i32)) as i16);
-        for y in last + 1..=y2 {
-            a = x1 + sa / dy12;
-            b = x0 + sb / dy02;
-            sa += dx12;
-            sb += dx02;
-            if a > b {
-                self.swap(&mut a, &mut b);
-            }
-            self.draw_fast_h_line(a, y, b - a + 1, color);
-        }
-    }
-
-    // Draw a bitmap
-    fn draw_bitmap(&mut self, x: i16, y: i16, bitmap: &[u8], w: i16, h: i16, color: u8) {
-        for j in 0..h {
-            for i in 0..w {
-                if (bitmap[(i as usize) + ((j / 8) as usize) * (w as usize)] & bv((1 << j % 8) as u8)) != 0 {
-                    self.draw_pixel(x + i, y + j, color);
-                }
-            }
-        }
-    }
 
     // Write a character
     fn write_char(&mut self, c: char) -> u8 {
@@ -426,44 +170,6 @@ impl Drawable for AdafruitGFX {
         }
     }
     
-   fn draw_char(&mut self, x: i16, y: i16, c: u8, color: u8, bg: u8, size: i16) {
-    if x >= self.width || y >= self.height || (x + (5 * size) - 1) < 0 || (y + (8 * size) - 1) < 0 {
-        return;
-    }
-
-    for i in 0..6 {
-        let mut line = if i == 5 { 0x0 } else { FONT[(c as usize) * 5 + i] };
-
-        for j in 0..8 {
-            if (line & 0x1) != 0 {
-                if size == 1 {
-                    self.draw_pixel(x + (i as i16), y + j, color);
-                } else {
-                    self.fill_rect(
-                        x + (i as i16) * size,
-                        y + j * size,
-                        size,
-                        size,
-                        color
-                    );
-                }
-            } else if bg != color {
-                if size == 1 {
-                    self.draw_pixel(x + (i as i16), y + j, bg);
-                } else {
-                    self.fill_rect(
-                        x + (i as i16) * size,
-                        y + j * size,
-                        size,
-                        size,
-                        bg
-                    );
-                }
-            }
-            line >>= 1; // Shift line to the right
-        }
-    }
-}
 
     // Set the display rotation
     fn set_rotation(&mut self, x: u8) {
@@ -471,18 +177,15 @@ impl Drawable for AdafruitGFX {
         match self.rotation {
             0 | 2 => {
                 self.width = self.raw_width;
-                self.height = self.raw_height;
+                self.height = self.visible_raw_height();
             }
             1 | 3 => {
-                self.width = self.raw_height;
+                self.width = self.visible_raw_height();
                 self.height = self.raw_width;
             }
             _ => {}
         }
     }
-    fn swap(&mut self, a: &mut i16, b: &mut i16) {
-        core::mem::swap(a, b);
-    }
 
     fn get_width(&self) -> i16 {
         self.width
@@ -521,3 +224,79 @@ impl Drawable for AdafruitGFX {
     }
 
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 8x8 source, lit in its left half
+    fn source() -> AdafruitGFX<8> {
+        let mut src = AdafruitGFX::<8>::new(8, 8);
+        src.fill_rect(0, 0, 4, 8, WHITE);
+        src
+    }
+
+    // Destination lit in the top half of the 8x8 area at (10, 20)
+    fn destination() -> AdafruitGFX {
+        let mut dst = AdafruitGFX::new(128, 64);
+        dst.fill_rect(10, 20, 8, 4, WHITE);
+        dst
+    }
+
+    #[test]
+    fn blit_raster_ops_at_an_offset() {
+        let ops: [(RasterOp, fn(bool, bool) -> bool); 4] = [
+            (RasterOp::Copy, |s, _| s),
+            (RasterOp::Or, |s, d| s | d),
+            (RasterOp::And, |s, d| s & d),
+            (RasterOp::Xor, |s, d| s ^ d),
+        ];
+        for (op, combine) in ops {
+            let mut dst = destination();
+            dst.blit(&source(), 10, 20, op);
+            for y in 16..32 {
+                for x in 6..22 {
+                    let (i, j) = (x - 10, y - 20);
+                    let expected = if (0..8).contains(&i) && (0..8).contains(&j) {
+                        combine(i < 4, j < 4)
+                    } else {
+                        false
+                    };
+                    assert_eq!(dst.get_pixel(x, y), if expected { WHITE } else { BLACK }, "{:?} pixel ({}, {})", op, x, y);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn blit_clips_at_the_edges() {
+        let mut dst: AdafruitGFX = AdafruitGFX::new(128, 64);
+        dst.blit(&source(), 124, 60, RasterOp::Copy);
+        dst.blit(&source(), -4, -4, RasterOp::Copy);
+        let lit: usize = dst.buffer.iter().map(|b| b.count_ones() as usize).sum();
+        // Bottom right: columns 124-127 of rows 60-63, top left: nothing of the left half is visible
+        assert_eq!(lit, 16);
+        for y in 60..64 {
+            for x in 124..128 {
+                assert_eq!(dst.get_pixel(x, y), WHITE);
+            }
+        }
+    }
+
+    #[test]
+    fn blit_follows_the_destination_rotation() {
+        let mut dst: AdafruitGFX = AdafruitGFX::new(128, 64);
+        dst.set_rotation(1);
+        dst.blit(&source(), 0, 0, RasterOp::Copy);
+        for j in 0..8 {
+            for i in 0..8 {
+                assert_eq!(dst.get_pixel(i, j), if i < 4 { WHITE } else { BLACK });
+            }
+        }
+        // Rotation 1 puts (x, y) at raw (raw_width - y - 1, x)
+        dst.set_rotation(0);
+        assert_eq!(dst.get_pixel(127, 0), WHITE);
+        assert_eq!(dst.get_pixel(120, 3), WHITE);
+        assert_eq!(dst.get_pixel(127, 4), BLACK);
+    }
+}
@@ -1,6 +1,5 @@
 #![no_std]
-// Uncomment this to turn off the builtin splash
-pub const NO_SPLASH_ADAFRUIT: bool = false; 
+// The builtin splash is controlled by the `adafruit-splash` cargo feature
 
 // Uncomment this to enable all functionality
 //const GFX_WANT_ABSTRACTS: bool = true; 
@@ -22,6 +22,8 @@ All text above must be included in any redistribution
 
 #![no_std]
 
+use crate::glcdfont::FONT;
+
 pub fn bv(bit: u8) -> u8{
     1 << bit
 }
@@ -29,6 +31,61 @@ pub fn bv(bit: u8) -> u8{
 pub const  BLACK:u8 = 0;
 pub const WHITE:u8 = 1;
 
+/// Off-screen drawing surface of any size, N >= width * ((height + 7) / 8)
+pub type Canvas<const N: usize> = AdafruitGFX<N>;
+
+/// How blit() combines a source pixel with the destination pixel
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RasterOp {
+    Copy,
+    Or,
+    And,
+    Xor,
+}
+
+/// A rectangle in display coordinates
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rect {
+    pub x: i16,
+    pub y: i16,
+    pub w: i16,
+    pub h: i16,
+}
+
+impl Rect {
+    pub fn new(x: i16, y: i16, w: i16, h: i16) -> Self {
+        Self { x, y, w, h }
+    }
+
+    /// Smallest rectangle covering both
+    pub fn union(&self, other: Rect) -> Rect {
+        let left = core::cmp::min(self.x, other.x);
+        let top = core::cmp::min(self.y, other.y);
+        let right = core::cmp::max(self.x + self.w, other.x + other.w);
+        let bottom = core::cmp::max(self.y + self.h, other.y + other.h);
+        Rect::new(left, top, right - left, bottom - top)
+    }
+}
+
+
+/// A monochrome bitmap and its size in pixels, the bit order depends on the draw_bitmap_* call
+#[derive(Clone, Copy, Debug)]
+pub struct Bitmap<'a> {
+    pub data: &'a [u8],
+    pub width: i16,
+    pub height: i16,
+}
+
+impl<'a> Bitmap<'a> {
+    pub fn new(data: &'a [u8], width: i16, height: i16) -> Self {
+        Self { data, width, height }
+    }
+
+    // Bytes per row of the row-major layouts
+    fn byte_width(&self) -> usize {
+        (self.width.max(0) as usize).div_ceil(8)
+    }
+}
 
 /**
  * This is a Text and Graphics element drawing class.
@@ -39,7 +96,7 @@ pub const WHITE:u8 = 1;
  * hardware based on application control.
  *
  */
-pub struct AdafruitGFX {
+pub struct AdafruitGFX<const N: usize = 1024> {
     pub raw_width: i16,     // 'raw' display width/height - never changes
     pub raw_height: i16,
     pub width: i16,         // dependent on rotation
@@ -51,8 +108,8 @@ pub struct AdafruitGFX {
     pub textsize: i16,
     pub rotation: u8,
     pub wrap: bool,         // If set, 'wrap' text at right edge of display
-    pub buffer: [u8; 1024], //Uncomment this line and comment the below if you have 128x64 oLED
-    //pub buffer: [u8; 512], // <-- fixed-size array syntax (128*32)/8
+    pub zoom: bool,         // controller doubles every row, only the top half of the RAM is visible
+    pub buffer: [u8; N],    // page-major, 1024 bytes covers a 128x64 oLED
 }
 
 /// Trait for drawable displays
@@ -76,52 +133,395 @@ pub trait Drawable {
     fn write_string(&mut self, value: &str)   ;
 
     /// Draw a horizontal line
-    fn draw_fast_h_line(&mut self, x: i16, y: i16, w: i16, color: u8);
+    fn draw_fast_h_line(&mut self, x: i16, y: i16, w: i16, color: u8) {
+        self.draw_line(x, y, x + w - 1, y, color);
+    }
 
     /// Draw a rectangle
-    fn draw_rect(&mut self, x: i16, y: i16, w: i16, h: i16, color: u8);
+    fn draw_rect(&mut self, x: i16, y: i16, w: i16, h: i16, color: u8) {
+        self.draw_fast_h_line(x, y, w, color);
+        self.draw_fast_h_line(x, y + h - 1, w, color);
+        self.draw_fast_v_line(x, y, h, color);
+        self.draw_fast_v_line(x + w - 1, y, h, color);
+    }
 
     /// Fill the entire display
-    fn fill_screen(&mut self, color: u8);
+    fn fill_screen(&mut self, color: u8) {
+        self.fill_rect(0, 0, self.get_width(), self.get_height(), color);
+    }
 
     /// Draw a circle
-    fn draw_circle(&mut self, x0: i16, y0: i16, r: i16, color: u8);
+    fn draw_circle(&mut self, x0: i16, y0: i16, r: i16, color: u8) {
+        let mut f: i32 = 1 - (r as i32);
+        let mut ddF_x = 1;
+        let mut ddF_y: i32 = -2 * (r as i32);
+        let mut x = 0;
+        let mut y = r;
+
+        self.draw_pixel(x0, y0 + r, color);
+        self.draw_pixel(x0, y0 - r, color);
+        self.draw_pixel(x0 + r, y0, color);
+        self.draw_pixel(x0 - r, y0, color);
+
+        while x < y {
+            if f >= 0 {
+                y -= 1;
+                ddF_y += 2;
+                f += ddF_y;
+            }
+            x += 1;
+            ddF_x += 2;
+            f += ddF_x;
+
+            self.draw_pixel(x0 + x, y0 + y, color);
+            self.draw_pixel(x0 - x, y0 + y, color);
+            self.draw_pixel(x0 + x, y0 - y, color);
+            self.draw_pixel(x0 - x, y0 - y, color);
+            self.draw_pixel(x0 + y, y0 + x, color);
+            self.draw_pixel(x0 - y, y0 + x, color);
+            self.draw_pixel(x0 + y, y0 - x, color);
+            self.draw_pixel(x0 - y, y0 - x, color);
+        }
+    }
 
     /// Draw circle helper
-    fn draw_circle_helper(&mut self, x0: i16, y0: i16, r: i16, cornername: u8, color: u8);
+    fn draw_circle_helper(&mut self, x0: i16, y0: i16, r: i16, cornername: u8, color: u8) {
+        let mut f: i32 = 1 - (r as i32);
+        let mut ddF_x = 1;
+        let mut ddF_y: i32 = -2 * (r as i32);
+        let mut x = 0;
+        let mut y = r;
+
+        while x < y {
+            if f >= 0 {
+                y -= 1;
+                ddF_y += 2;
+                f += ddF_y;
+            }
+            x += 1;
+            ddF_x += 2;
+            f += ddF_x;
+
+            if (cornername & 0x4) != 0 {
+                self.draw_pixel(x0 + x, y0 + y, color);
+                self.draw_pixel(x0 + y, y0 + x, color);
+            }
+
+            if (cornername & 0x2) != 0 {
+                self.draw_pixel(x0 + x, y0 - y, color);
+                self.draw_pixel(x0 + y, y0 - x, color);
+            }
+
+            if (cornername & 0x8) != 0 {
+                self.draw_pixel(x0 - y, y0 + x, color);
+                self.draw_pixel(x0 - x, y0 + y, color);
+            }
+
+            if (cornername & 0x1) != 0 {
+                self.draw_pixel(x0 - y, y0 - x, color);
+                self.draw_pixel(x0 - x, y0 - y, color);
+            }
+        }
+    }
 
     /// Fill a circle
-    fn fill_circle(&mut self, x0: i16, y0: i16, r: i16, color: u8);
+    fn fill_circle(&mut self, x0: i16, y0: i16, r: i16, color: u8) {
+        self.draw_fast_v_line(x0, y0 - r, 2 * r + 1, color);
+        self.fill_circle_helper(x0, y0, r, 3, 0, color);
+    }
 
     /// Fill circle helper
-    fn fill_circle_helper(&mut self, x0: i16,y0: i16,r: i16, cornername: u8, delta: i16,color: u8,);
+    fn fill_circle_helper(
+        &mut self,
+        x0: i16,
+        y0: i16,
+        r: i16,
+        cornername: u8,
+        delta: i16,
+        color: u8
+    ) {
+        let mut f: i32 = 1 - (r as i32);
+        let mut ddF_x = 1;
+        let mut ddF_y: i32 = -2 * (r as i32);
+        let mut x = 0;
+        let mut y = r;
+
+        while x < y {
+            if f >= 0 {
+                y -= 1;
+                ddF_y += 2;
+                f += ddF_y;
+            }
+            x += 1;
+            ddF_x += 2;
+            f += ddF_x;
+
+            if (cornername & 0x1) != 0 {
+                self.draw_fast_v_line(x0 + x, y0 - y, 2 * y + 1 + delta, color);
+                self.draw_fast_v_line(x0 + y, y0 - x, 2 * x + 1 + delta, color);
+            }
+
+            if (cornername & 0x2) != 0 {
+                self.draw_fast_v_line(x0 - x, y0 - y, 2 * y + 1 + delta, color);
+                self.draw_fast_v_line(x0 - y, y0 - x, 2 * x + 1 + delta, color);
+            }
+        }
+    }
 
     /// Draw a triangle
-    fn draw_triangle(&mut self, x0: i16, y0: i16, x1: i16, y1: i16, x2: i16, y2: i16, color: u8);
+    fn draw_triangle(
+        &mut self,
+        x0: i16,
+        y0: i16,
+        x1: i16,
+        y1: i16,
+        x2: i16,
+        y2: i16,
+        color: u8
+    ) {
+        self.draw_line(x0, y0, x1, y1, color);
+        self.draw_line(x1, y1, x2, y2, color);
+        self.draw_line(x2, y2, x0, y0, color);
+    }
 
     /// Fill a triangle
-    fn fill_triangle(&mut self, x0: i16, y0: i16, x1: i16, y1: i16, x2: i16, y2: i16, color: u8);
+    fn fill_triangle(
+        &mut self,
+        mut x0: i16,
+        mut y0: i16,
+        mut x1: i16,
+        mut y1: i16,
+        mut x2: i16,
+        mut y2: i16,
+        color: u8
+    ) {
+        // Sort coordinates by Y order (y2 >= y1 >= y0)
+        if y0 > y1 {
+            self.swap(&mut y0, &mut y1);
+            self.swap(&mut x0, &mut x1);
+        }
+        if y1 > y2 {
+            self.swap(&mut y2, &mut y1);
+            self.swap(&mut x2, &mut x1);
+        }
+        if y0 > y1 {
+            self.swap(&mut y0, &mut y1);
+            self.swap(&mut x0, &mut x1);
+        }
+
+        if y0 == y2 {
+            let mut a = x0;
+            let mut b = x0;
+            if x1 < a {
+                a = x1;
+            } else if x1 > b {
+                b = x1;
+            }
+            if x2 < a {
+                a = x2;
+            } else if x2 > b {
+                b = x2;
+            }
+            self.draw_fast_h_line(a, y0, b - a + 1, color);
+            return;
+        }
+
+        let dx01 = x1 - x0;
+        let dy01 = y1 - y0;
+        let dx02 = x2 - x0;
+        let dy02 = y2 - y0;
+        let dx12 = x2 - x1;
+        let dy12 = y2 - y1;
+        let mut sa = 0;
+        let mut sb = 0;
+
+        // Upper part, including row y1 only when the lower edge is flat
+        let last = if y1 == y2 { y1 } else { y1 - 1 };
+
+        for y in y0..=last {
+            let mut a = x0 + sa / dy01;
+            let mut b = x0 + sb / dy02;
+            sa += dx01;
+            sb += dx02;
+            if a > b {
+                self.swap(&mut a, &mut b);
+            }
+            self.draw_fast_h_line(a, y, b - a + 1, color);
+        }
+
+        // Lower part, from the first row after the upper one
+        sa = dx12 * (last + 1 - y1);
+        sb = dx02 * (last + 1 - y0);
+        for y in last + 1..=y2 {
+            let mut a = x1 + sa / dy12;
+            let mut b = x0 + sb / dy02;
+            sa += dx12;
+            sb += dx02;
+            if a > b {
+                self.swap(&mut a, &mut b);
+            }
+            self.draw_fast_h_line(a, y, b - a + 1, color);
+        }
+    }
 
     /// Draw a rounded rectangle
-    fn draw_round_rect(&mut self, x0: i16, y0: i16, w: i16, h: i16, radius: i16, color: u8);
+    fn draw_round_rect(&mut self, x: i16, y: i16, w: i16, h: i16, r: i16, color: u8) {
+        self.draw_fast_h_line(x + r, y, w - 2 * r, color); // Top
+        self.draw_fast_h_line(x + r, y + h - 1, w - 2 * r, color); // Bottom
+        self.draw_fast_v_line(x, y + r, h - 2 * r, color); // Left
+        self.draw_fast_v_line(x + w - 1, y + r, h - 2 * r, color); // Right
+        self.draw_circle_helper(x + r, y + r, r, 1, color); // Top-left
+        self.draw_circle_helper(x + w - r - 1, y + r, r, 2, color); // Top-right
+        self.draw_circle_helper(x + w - r - 1, y + h - r - 1, r, 4, color); // Bottom-right
+        self.draw_circle_helper(x + r, y + h - r - 1, r, 8, color); // Bottom-left
+    }
 
     /// Fill a rounded rectangle
-    fn fill_round_rect(&mut self, x0: i16, y0: i16, w: i16, h: i16, radius: i16, color: u8);
+    fn fill_round_rect(&mut self, x: i16, y: i16, w: i16, h: i16, r: i16, color: u8) {
+        self.fill_rect(x + r, y, w - 2 * r, h, color);
+        self.fill_circle_helper(x + w - r - 1, y + r, r, 1, h - 2 * r - 1, color);
+        self.fill_circle_helper(x + r, y + r, r, 2, h - 2 * r - 1, color);
+    }
 
-    /// Draw a bitmap
-    fn draw_bitmap(&mut self, x: i16, y: i16, bitmap: &[u8], w: i16, h: i16, color: u8);
+    /// Draw a bitmap stored in the SSD1306 page layout, set bits only
+    fn draw_bitmap(&mut self, x: i16, y: i16, bitmap: &[u8], w: i16, h: i16, color: u8) {
+        self.draw_bitmap_pagemajor(x, y, &Bitmap::new(bitmap, w, h), color, None);
+    }
+
+    /// Draw a row-major bitmap, MSB first (Adafruit drawBitmap, image2cpp "horizontal")
+    /// If `bg` is given, clear bits are painted with it instead of being transparent
+    fn draw_bitmap_rowmajor(&mut self, x: i16, y: i16, bitmap: &Bitmap, color: u8, bg: Option<u8>) {
+        let byte_width = bitmap.byte_width();
+        for j in 0..bitmap.height {
+            for i in 0..bitmap.width {
+                let byte = bitmap.data[(j as usize) * byte_width + (i / 8) as usize];
+                if (byte & (0x80 >> (i % 8))) != 0 {
+                    self.draw_pixel(x + i, y + j, color);
+                } else if let Some(bg) = bg {
+                    self.draw_pixel(x + i, y + j, bg);
+                }
+            }
+        }
+    }
+
+    /// Draw a page-major bitmap, one byte per 8 vertical pixels, LSB on top (SSD1306 GDDRAM layout)
+    /// If `bg` is given, clear bits are painted with it instead of being transparent
+    fn draw_bitmap_pagemajor(&mut self, x: i16, y: i16, bitmap: &Bitmap, color: u8, bg: Option<u8>) {
+        for j in 0..bitmap.height {
+            for i in 0..bitmap.width {
+                let byte = bitmap.data[(i as usize) + ((j / 8) as usize) * (bitmap.width as usize)];
+                if (byte & bv((j % 8) as u8)) != 0 {
+                    self.draw_pixel(x + i, y + j, color);
+                } else if let Some(bg) = bg {
+                    self.draw_pixel(x + i, y + j, bg);
+                }
+            }
+        }
+    }
+
+    /// Draw a row-major bitmap, LSB first (XBM files as exported by GIMP)
+    /// If `bg` is given, clear bits are painted with it instead of being transparent
+    fn draw_xbitmap(&mut self, x: i16, y: i16, bitmap: &Bitmap, color: u8, bg: Option<u8>) {
+        let byte_width = bitmap.byte_width();
+        for j in 0..bitmap.height {
+            for i in 0..bitmap.width {
+                let byte = bitmap.data[(j as usize) * byte_width + (i / 8) as usize];
+                if (byte & bv((i % 8) as u8)) != 0 {
+                    self.draw_pixel(x + i, y + j, color);
+                } else if let Some(bg) = bg {
+                    self.draw_pixel(x + i, y + j, bg);
+                }
+            }
+        }
+    }
 
     /// Draw a line
-    fn draw_line(&mut self, x0: i16, y0: i16, x1: i16, y1: i16, color: u8);
+    fn draw_line(&mut self, x0: i16, y0: i16, x1: i16, y1: i16, color: u8) {
+        let dx = ((x1 as i32) - (x0 as i32)).abs();
+        let dy = ((y1 as i32) - (y0 as i32)).abs();
+        let steep = dy > dx;
+
+        let (x0, y0, x1, y1) = if steep {
+            (y0, x0, y1, x1) // Swap x and y
+        } else {
+            (x0, y0, x1, y1)
+        };
+
+        let (x0, x1) = if x0 > x1 { (x1, x0) } else { (x0, x1) };
+
+        let dx: i32 = (x1 as i32) - (x0 as i32);
+        let dy = ((y1 as i32) - (y0 as i32)).abs();
+        let mut err: i32 = dx / 2;
+        let ystep: i32 = if y0 < y1 { 1 } else { -1 };
+
+        let mut y: i32 = y0 as i32;
+
+        for x in x0..=x1 {
+            if steep {
+                self.draw_pixel(y as i16, x, color);
+            } else {
+                self.draw_pixel(x, y as i16, color);
+            }
+
+            err -= dy;
+            if err < 0 {
+                y += ystep;
+                err += dx;
+            }
+        }
+    }
 
     /// Draw a vertical line
-    fn draw_fast_v_line(&mut self, x: i16, y: i16, h: i16, color: u8);
+    fn draw_fast_v_line(&mut self, x: i16, y: i16, h: i16, color: u8) {
+        self.draw_line(x, y, x, y + h - 1, color);
+    }
 
     /// Fill a rectangle
-    fn fill_rect(&mut self, x: i16, y: i16, w: i16, h: i16, color: u8);
+    fn fill_rect(&mut self, x: i16, y: i16, w: i16, h: i16, color: u8) {
+        for i in x..x + w {
+            self.draw_fast_v_line(i, y, h, color);
+        }
+    }
 
     /// Draw a text character at a specified pixel location
-    fn draw_char(&mut self, x: i16, y: i16, c: u8, color: u8, bg: u8, size: i16);
+    fn draw_char(&mut self, x: i16, y: i16, c: u8, color: u8, bg: u8, size: i16) {
+        if x >= self.get_width() || y >= self.get_height() || (x + (5 * size) - 1) < 0 || (y + (8 * size) - 1) < 0 {
+            return;
+        }
+
+        for i in 0..6 {
+            let mut line = if i == 5 { 0x0 } else { FONT[(c as usize) * 5 + i] };
+
+            for j in 0..8 {
+                if (line & 0x1) != 0 {
+                    if size == 1 {
+                        self.draw_pixel(x + (i as i16), y + j, color);
+                    } else {
+                        self.fill_rect(
+                            x + (i as i16) * size,
+                            y + j * size,
+                            size,
+                            size,
+                            color
+                        );
+                    }
+                } else if bg != color {
+                    if size == 1 {
+                        self.draw_pixel(x + (i as i16), y + j, bg);
+                    } else {
+                        self.fill_rect(
+                            x + (i as i16) * size,
+                            y + j * size,
+                            size,
+                            size,
+                            bg
+                        );
+                    }
+                }
+                line >>= 1; // Shift line to the right
+            }
+        }
+    }
 
     /// Get the width of the display in pixels
     fn get_width(&self) -> i16;
@@ -150,5 +550,83 @@ pub trait Drawable {
     /// Get the current rotation
     fn get_rotation(&mut self) -> u8;
 
-    fn swap(&mut self, a: &mut i16, b: &mut i16);
+    fn swap(&mut self, a: &mut i16, b: &mut i16) {
+        core::mem::swap(a, b);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 10x3 test image, width not a multiple of 8
+    const PATTERN: [&str; 3] = [
+        "#.#.....#.",
+        ".#......##",
+        "####.....#",
+    ];
+    const ROWMAJOR: [u8; 6] = [0xa0, 0x80, 0x40, 0xc0, 0xf0, 0x40];
+    const XBM: [u8; 6] = [0x05, 0x01, 0x02, 0x03, 0x0f, 0x02];
+    const PAGEMAJOR: [u8; 10] = [0x05, 0x06, 0x05, 0x04, 0x00, 0x00, 0x00, 0x00, 0x03, 0x06];
+
+    // Draw PATTERN's bytes at (3, 5) and compare every pixel of it
+    fn check(draw: fn(&mut AdafruitGFX, &Bitmap, Option<u8>), data: &[u8]) {
+        let bitmap = Bitmap::new(data, 10, 3);
+
+        let mut gfx = AdafruitGFX::new(128, 64);
+        draw(&mut gfx, &bitmap, None);
+        for (j, row) in PATTERN.iter().enumerate() {
+            for (i, c) in row.chars().enumerate() {
+                let expected = if c == '#' { WHITE } else { BLACK };
+                assert_eq!(gfx.get_pixel(3 + i as i16, 5 + j as i16), expected, "pixel ({}, {})", i, j);
+            }
+        }
+
+        // With a background clear bits are painted, pixels outside the bitmap are untouched
+        let mut gfx = AdafruitGFX::new(128, 64);
+        gfx.fill_screen(WHITE);
+        draw(&mut gfx, &bitmap, Some(BLACK));
+        for (j, row) in PATTERN.iter().enumerate() {
+            for (i, c) in row.chars().enumerate() {
+                let expected = if c == '#' { WHITE } else { BLACK };
+                assert_eq!(gfx.get_pixel(3 + i as i16, 5 + j as i16), expected, "pixel ({}, {}) with bg", i, j);
+            }
+        }
+        assert_eq!(gfx.get_pixel(2, 5), WHITE);
+        assert_eq!(gfx.get_pixel(13, 5), WHITE);
+        assert_eq!(gfx.get_pixel(3, 8), WHITE);
+    }
+
+    #[test]
+    fn fill_triangle_any_vertex_order() {
+        let vertices = [(2, 3), (12, 13), (2, 13)];
+        let orders = [[0, 1, 2], [0, 2, 1], [1, 0, 2], [1, 2, 0], [2, 0, 1], [2, 1, 0]];
+        for order in orders {
+            let [a, b, c] = order.map(|i| vertices[i]);
+            let mut gfx: AdafruitGFX = AdafruitGFX::new(128, 64);
+            gfx.fill_triangle(a.0, a.1, b.0, b.1, c.0, c.1, WHITE);
+            // Row y of the right triangle covers x = 2..=y - 1
+            for y in 0..20 {
+                for x in 0..20 {
+                    let inside = (3..=13).contains(&y) && (2..=y - 1).contains(&x);
+                    assert_eq!(gfx.get_pixel(x, y), if inside { WHITE } else { BLACK }, "order {:?} pixel ({}, {})", order, x, y);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rowmajor_bit_order() {
+        check(|gfx, bitmap, bg| gfx.draw_bitmap_rowmajor(3, 5, bitmap, WHITE, bg), &ROWMAJOR);
+    }
+
+    #[test]
+    fn xbitmap_bit_order() {
+        check(|gfx, bitmap, bg| gfx.draw_xbitmap(3, 5, bitmap, WHITE, bg), &XBM);
+    }
+
+    #[test]
+    fn pagemajor_bit_order() {
+        check(|gfx, bitmap, bg| gfx.draw_bitmap_pagemajor(3, 5, bitmap, WHITE, bg), &PAGEMAJOR);
+    }
 }
@@ -13,7 +13,7 @@ products from Adafruit!
 
 Written by Limor Fried/Ladyada  for Adafruit Industries.  
 BSD license, check license.txt for more information
-All text above, and the splash screen below must be included in any redistribution
+All text above, and the splash screen must be included in any redistribution
 *********************************************************************/
 
 /*
@@ -23,18 +23,26 @@ All text above, and the splash screen below must be included in any redistributi
 
 #![no_std]
 
-use crate::adafruit_gfx_config::NO_SPLASH_ADAFRUIT;
-use crate::adafruit_gfx_h::AdafruitGFX;
+use crate::adafruit_gfx_h::{ AdafruitGFX, Rect };
+use crate::clock::{ Clock, ClockDuration };
+use crate::config::{ Config, Orientation, VccSource };
+use crate::controller::Controller;
+use crate::window::FlushWindow;
 use crate::adafruit_ssd1306_h::*;
+use crate::splash::{ Splash, SplashImage };
+use crate::scroll::ScrollConfig;
+use crate::ssd1306_command::{ Command, FadeInterval, FadeMode, ScrollDirection, VcomhLevel };
 use embedded_hal::{ delay::DelayNs };
+use embedded_hal::digital::{ self, OutputPin };
+use embedded_hal::i2c::{ self as i2c_new, I2c, Operation };
 use embedded_hal_old::blocking::i2c;
+use embedded_hal_old::digital::v2;
 
 use core::cmp::min;
 
 use core::result::Result;
 use crate::adafruit_ssd1306::Result::Ok;
 
-use stm32f7xx_hal::{ gpio::{ Output, PushPull, PB11, PG6 } };
 
 pub enum SSD1306Commands {
     SSD1306_SETCONTRAST = 0x81,
@@ -58,6 +66,19 @@ pub enum SSD1306Commands {
     SSD1306_COMSCANDEC = 0xc8,
     SSD1306_SEGREMAP = 0xa0,
     SSD1306_CHARGEPUMP = 0x8d,
+    SSD1306_COLUMNADDR = 0x21,
+    SSD1306_PAGEADDR = 0x22,
+    SSD1306_PAGESTART = 0xb0,
+    SSD1306_NOP = 0xe3,
+
+    //Fade out / blink and zoom
+    SSD1306_FADEBLINK = 0x23,
+    SSD1306_ZOOMIN = 0xd6,
+
+    //SH1106 and SSD1305
+    SH1106_SETDCDC = 0xad,      // SSD1305: master configuration
+    SSD1305_SETAREACOLOUR = 0xd8,
+    SSD1305_SETLUT = 0x91,
     
     //Scrolling
     SSD1306_HORIZONTAL_SCROLL_R = 0x26,
@@ -71,9 +92,14 @@ pub enum SSD1306Commands {
     SSD1306_ACTIVATE_SCROLL = 0x2f,
     SSD1306_DEACTIVATE_SCROLL = 0x2e,
 }
-use SSD1306Commands::*;
 use cortex_m::{ delay::Delay as CortexDelay, prelude::_embedded_hal_blocking_i2c_Write };
 
+// Number of contrast steps used when fading out a splash screen
+const SPLASH_FADE_STEPS: u32 = 16;
+
+// Brightness levels below this also lower precharge and VCOMH
+const LOW_BRIGHTNESS_LEVEL: u16 = 32;
+
 //Work around problems you get due to mismatch of trait and HAL - Start
 pub struct DelayWrapper {
     inner: CortexDelay,
@@ -93,40 +119,127 @@ impl DelayNs for DelayWrapper {
     }
 }
 
+/// Makes an embedded-hal 0.2 blocking I2C bus (such as the stm32f7xx-hal BlockingI2c)
+/// usable where the driver and embedded-hal-bus expect an embedded-hal 1.0 I2c
+pub struct I2cWrapper<I2C> {
+    inner: I2C,
+}
+
+impl<I2C> I2cWrapper<I2C> {
+    pub fn new(inner: I2C) -> Self {
+        I2cWrapper { inner }
+    }
+
+    pub fn release(self) -> I2C {
+        self.inner
+    }
+}
+
+/// Error of the wrapped 0.2 bus, reported as ErrorKind::Other
+#[derive(Debug)]
+pub struct I2cWrapperError<E>(pub E);
+
+impl<E: core::fmt::Debug> i2c_new::Error for I2cWrapperError<E> {
+    fn kind(&self) -> i2c_new::ErrorKind {
+        i2c_new::ErrorKind::Other
+    }
+}
+
+impl<I2C, E: core::fmt::Debug> i2c_new::ErrorType for I2cWrapper<I2C>
+    where I2C: i2c::Write<Error = E> + i2c::Read<Error = E> + i2c::WriteRead<Error = E>
+{
+    type Error = I2cWrapperError<E>;
+}
+
+impl<I2C, E: core::fmt::Debug> I2c for I2cWrapper<I2C>
+    where I2C: i2c::Write<Error = E> + i2c::Read<Error = E> + i2c::WriteRead<Error = E>
+{
+    // The 0.2 traits have no transaction: a write followed by a read becomes one
+    // write_read, everything else is sent operation by operation.
+    // Unlike the embedded-hal 1.0 contract, consecutive writes therefore go out as separate
+    // transfers, each with its own START and STOP. The display driver only sends single
+    // writes, so it is not affected
+    fn transaction(&mut self, address: u8, operations: &mut [Operation<'_>]) -> Result<(), Self::Error> {
+        let mut i = 0;
+        while i < operations.len() {
+            match operations.split_at_mut(i + 1) {
+                ([.., Operation::Write(bytes)], [Operation::Read(buffer), ..]) => {
+                    self.inner.write_read(address, bytes, buffer).map_err(I2cWrapperError)?;
+                    i += 2;
+                }
+                ([.., Operation::Write(bytes)], _) => {
+                    self.inner.write(address, bytes).map_err(I2cWrapperError)?;
+                    i += 1;
+                }
+                ([.., Operation::Read(buffer)], _) => {
+                    self.inner.read(address, buffer).map_err(I2cWrapperError)?;
+                    i += 1;
+                }
+                _ => i += 1,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Makes an embedded-hal 0.2 output pin (such as the stm32f7xx-hal pins) usable as
+/// the reset pin, which the driver takes as an embedded-hal 1.0 OutputPin
+pub struct OutputPinWrapper<P> {
+    inner: P,
+}
+
+impl<P> OutputPinWrapper<P> {
+    pub fn new(inner: P) -> Self {
+        OutputPinWrapper { inner }
+    }
+
+    pub fn release(self) -> P {
+        self.inner
+    }
+}
+
+/// Error of the wrapped 0.2 pin, reported as ErrorKind::Other
+#[derive(Debug)]
+pub struct OutputPinWrapperError<E>(pub E);
+
+impl<E: core::fmt::Debug> digital::Error for OutputPinWrapperError<E> {
+    fn kind(&self) -> digital::ErrorKind {
+        digital::ErrorKind::Other
+    }
+}
+
+impl<P: v2::OutputPin<Error = E>, E: core::fmt::Debug> digital::ErrorType for OutputPinWrapper<P> {
+    type Error = OutputPinWrapperError<E>;
+}
+
+impl<P: v2::OutputPin<Error = E>, E: core::fmt::Debug> OutputPin for OutputPinWrapper<P> {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.inner.set_low().map_err(OutputPinWrapperError)
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.inner.set_high().map_err(OutputPinWrapperError)
+    }
+}
+
 //----------------
+/// Reset pin of the display
 pub trait MyGpioPin {
     fn set_high(&mut self) -> Result<(), Self::Error>;
     fn set_low(&mut self) -> Result<(), Self::Error>;
-    fn toggle(&mut self) -> Result<(), Self::Error>;
-    fn is_set_high(&self) -> Result<bool, Self::Error>;
     type Error;
 }
-impl MyGpioPin for PB11<Output<PushPull>> {
-    type Error = ();
+
+// Any embedded-hal 1.0 output pin, wrap 0.2 pins in OutputPinWrapper
+impl<P: OutputPin> MyGpioPin for P {
+    type Error = P::Error;
 
     fn set_high(&mut self) -> Result<(), Self::Error> {
-        self.set_high();
-        Ok(())
+        OutputPin::set_high(self)
     }
 
     fn set_low(&mut self) -> Result<(), Self::Error> {
-        self.set_low();
-        Ok(())
-    }
-
-    fn toggle(&mut self) -> Result<(), Self::Error> {
-        // Toggle implementation might depend on the specific pin
-        if self.is_set_high() {
-            Ok(())
-        } else {
-            self.set_high();
-            Ok(())
-        }
-    }
-
-    fn is_set_high(&self) -> Result<bool, Self::Error> {
-        // You may need to implement this depending on your pin type
-        Ok(true) // Placeholder; replace with actual logic
+        OutputPin::set_low(self)
     }
 }
 
@@ -135,6 +248,79 @@ impl MyGpioPin for PB11<Output<PushPull>> {
 pub enum AdafruitSSD1306Error<E> {
     CommandTooLong,
     I2cError(E), // Wrap the specific I2C error type
+    NoDevice,    // nothing acknowledged on SSD1306_I2C_ADDRESS or SSD1306_I2C_ADDRESS_ALT
+}
+
+/// Addresses tried by probe(), in order
+pub const PROBE_ADDRESSES: [u8; 2] = [SSD1306_I2C_ADDRESS, SSD1306_I2C_ADDRESS_ALT];
+
+/// What probe() sends: a NOP command, harmless to a display that is already running
+pub const PROBE_WRITE: [u8; 2] = [0x00, SSD1306Commands::SSD1306_NOP as u8];
+
+/// Find the display on the bus before handing it to `AdafruitSSD1306::new()`.
+/// Sends a NOP command to 0x3C then 0x3D and returns the first address that acknowledges.
+/// The controller must be out of reset: drive its reset pin high and wait a moment first
+pub fn probe<I2C, E>(i2c: &mut I2C) -> Result<u8, AdafruitSSD1306Error<E>>
+    where I2C: I2c<Error = E>
+{
+    for address in PROBE_ADDRESSES {
+        if i2c.write(address, &PROBE_WRITE).is_ok() {
+            return Ok(address);
+        }
+    }
+    Err(AdafruitSSD1306Error::NoDevice)
+}
+
+// Send one command with its arguments in a single transfer
+fn write_command<I2C: I2c>(i2c: &mut I2C, address: u8, cmd: Command) {
+    let bytes = cmd.bytes();
+    let bytes = bytes.as_slice();
+    let mut buff = [0; 8];
+    buff[0] = 0; // Command Mode, the whole command in one transfer
+    buff[1..=bytes.len()].copy_from_slice(bytes);
+    let _ = i2c.write(address, &buff[..=bytes.len()]);
+}
+
+// Send `data` in 16-byte chunks
+fn send_data<I2C: I2c>(i2c: &mut I2C, address: u8, data: &[u8]) {
+    let mut buff = [0; 17];
+    buff[0] = 0x40; // Data Mode
+    for chunk in data.chunks(16) {
+        buff[1..=chunk.len()].copy_from_slice(chunk);
+        let _e = i2c.write(address, &buff[..=chunk.len()]); // Send only the filled part
+    }
+}
+
+// Send `window` of `source`, a buffer `width` raw columns wide
+fn flush_window<I2C: I2c>(i2c: &mut I2C, address: u8, window: FlushWindow, width: i16, source: &[u8]) {
+    for cmd in window.window_commands().iter().flatten() {
+        write_command(i2c, address, *cmd);
+    }
+    for page in window.pages() {
+        for cmd in window.page_commands(page).iter().flatten() {
+            write_command(i2c, address, *cmd);
+        }
+        send_data(i2c, address, &source[window.buffer_range(page, width)]);
+    }
+}
+
+// Runs of consecutive pages set in `pages` (bit 0 = page 0) below `count`, as (first, last)
+fn page_runs(pages: u8, count: u8) -> impl Iterator<Item = (u8, u8)> {
+    let mut page = 0;
+    core::iter::from_fn(move || {
+        while page < count && pages & (1 << page) == 0 {
+            page += 1;
+        }
+        if page >= count {
+            return None;
+        }
+        let first = page;
+        while page + 1 < count && pages & (1 << (page + 1)) != 0 {
+            page += 1;
+        }
+        page += 1;
+        Some((first, page - 1))
+    })
 }
 
 impl<I2C, GPIO, DELAY, E> Display<I2C, GPIO, DELAY>
@@ -142,183 +328,294 @@ impl<I2C, GPIO, DELAY, E> Display<I2C, GPIO, DELAY>
     where
         DELAY: DelayNs, // Ensure DELAY implements the required trait
         GPIO: MyGpioPin, // Ensure GPIO implements the required GPIO pin trait
-        I2C: i2c::Write<Error = E> + i2c::WriteRead<Error = E> // Combined Write and WriteRead
+        I2C: I2c<Error = E> // Combined Write and WriteRead
 {
-    fn new(ni2c: I2C, n_rst: GPIO, delay: DELAY, ngfx: AdafruitGFX) -> Self {
+    fn new(ni2c: I2C, address: u8, n_rst: GPIO, delay: DELAY, ngfx: AdafruitGFX, controller: Controller) -> Self {
         AdafruitSSD1306 {
             rst: n_rst,
             i2c: ni2c,
-            address: 0x3c,
+            address,
             delay,
+            controller,
+            contrast: 0x8f,
+            config: Config::for_panel(ngfx.raw_width, ngfx.raw_height),
+            inverted: false,
+            scroll: None,
+            power: PowerState::On,
+            idle_timeout: None,
+            last_activity: None,
+            activity: false,
+            #[cfg(feature = "double-buffer")]
+            front: [0; 1024],
+            #[cfg(feature = "double-buffer")]
+            front_shown: false,
+            start_line: 0,
             gfx: ngfx,
         }
     }
 
-    fn begin(&mut self, vccstate: u8) {
+    fn begin(&mut self, config: &Config) {
         let _ = self.rst.set_high();
         // VDD (3.3V) goes high at start, lets just chill for a ms
         self.delay.delay_ms(1u32);
 
         // Bring reset low
-        self.rst.set_low();
+        let _ = self.rst.set_low();
         // Wait 10ms
         self.delay.delay_ms(10u32);
 
         // Bring out of reset
-        self.rst.set_high();
+        let _ = self.rst.set_high();
 
-        self.command(SSD1306_DISPLAYOFF as u8);
-        self.command(SSD1306_SETDISPLAYCLOCKDIV as u8);
-        self.command(0x80); // The suggested ratio 0x80
+        self.config = *config;
+        self.contrast = config.contrast;
+        self.inverted = false;
+        self.scroll = None;
+        self.power = PowerState::On;
+        self.start_line = 0;
+        #[cfg(feature = "double-buffer")]
+        {
+            self.front_shown = false;
+        }
 
-        self.command(SSD1306_SETMULTIPLEX as u8);
-        self.command((self.gfx.raw_height as u8) - 1);
+        self.send_commands(&config.init_commands(self.controller));
+    }
 
-        self.command(SSD1306_SETDISPLAYOFFSET as u8);
-        self.command(0x0); // No offset
+    fn invert_display(&mut self, i: bool) {
+        self.inverted = i;
+        self.send_command(Command::Invert(i));
+    }
+
+    fn set_orientation(&mut self, orientation: Orientation) {
+        self.config.orientation = orientation;
+        self.send_commands(&orientation.commands());
+    }
 
-        self.command((SSD1306_SETSTARTLINE as u8) | 0x0); // Line #0
+    fn set_contrast(&mut self, contrast: u8) {
+        self.contrast = contrast;
+        self.send_command(Command::Contrast(contrast));
+    }
+
+    // Dimming keeps the stored contrast so dim(false) brings it back
+    fn dim(&mut self, dim: bool) {
+        self.send_command(Command::Contrast(if dim { 0 } else { self.contrast }));
+    }
+
+    fn set_brightness(&mut self, level: u8) {
+        // Contrast is roughly linear in current, the eye is not: square the level
+        let level = level as u16;
+        let contrast = if level == 0 { 0 } else { core::cmp::max(1, level * level / 255) as u8 };
+        self.set_contrast(contrast);
+
+        if level < LOW_BRIGHTNESS_LEVEL {
+            // Below what contrast alone can reach: shorten the precharge and drop VCOMH
+            self.send_commands(&[
+                Command::Precharge { phase1: 1, phase2: 1 },
+                Command::VcomhDeselect(VcomhLevel::V065),
+            ]);
+        } else {
+            self.send_commands(&[
+                Command::Precharge { phase1: self.config.precharge.0, phase2: self.config.precharge.1 },
+                Command::VcomhDeselect(self.config.vcomh),
+            ]);
+        }
+    }
+
+    // Send the display buffer out to the display
+    fn show(&mut self) {
+        self.activity = true;
+        if self.power == PowerState::IdleSleep {
+            self.wake();
+        }
+        self.send_command(Command::StartLine(self.start_line));
+        self.send_display_buffer();
+        // Keep the front buffer matching the panel for the next show_changed()
+        #[cfg(feature = "double-buffer")]
+        {
+            self.front.copy_from_slice(&self.gfx.buffer);
+            self.front_shown = true;
+        }
+    }
 
-        self.command(SSD1306_CHARGEPUMP as u8);
-        self.command(if vccstate == SSD1306_EXTERNALVCC { 0x10 } else { 0x14 });
+    // Swap first so the frame is sent from the front buffer, which nothing draws into.
+    // After the swap the back buffer holds the frame on the panel, the diff source
+    #[cfg(feature = "double-buffer")]
+    fn show_changed(&mut self) {
+        self.activity = true;
+        if self.power == PowerState::IdleSleep {
+            self.wake();
+        }
+        self.swap_buffers();
+        let count = (self.gfx.raw_height / 8) as u8;
+        let dirty = if self.front_shown { self.dirty_pages() } else { ((1u16 << count) - 1) as u8 };
+        let last_column = (self.gfx.raw_width - 1) as u8;
+        for (first, last) in page_runs(dirty, count) {
+            let window = FlushWindow::new(self.controller, &self.config, 0, last_column, first, last);
+            flush_window(&mut self.i2c, self.address, window, self.gfx.raw_width, &self.front);
+        }
+        self.front_shown = true;
+    }
 
-        self.command(SSD1306_MEMORYMODE as u8);
-        self.command(0x00); // 0x0 acts like KS0108
+    fn show_region(&mut self, region: Rect) {
+        self.activity = true;
+        if self.power == PowerState::IdleSleep {
+            self.wake();
+        }
+        if let Some(raw) = self.gfx.raw_rect(region) {
+            self.flush_area(
+                raw.x as u8,
+                (raw.x + raw.w - 1) as u8,
+                (raw.y / 8) as u8,
+                ((raw.y + raw.h - 1) / 8) as u8,
+            );
+        }
+    }
 
-        self.command((SSD1306_SEGREMAP as u8) | 0x1);
-        self.command(SSD1306_COMSCANDEC as u8);
+    fn set_start_line(&mut self, line: u8) {
+        self.start_line = line % (self.gfx.raw_height as u8);
+        self.send_command(Command::StartLine(self.start_line));
+    }
 
-        self.command(SSD1306_SETCOMPINS as u8);
-        self.command(if self.gfx.raw_height == 32 { 0x02 } else { 0x12 }); // Calculate based on _rawHeight
+    fn scroll_vertical_soft(&mut self, rows: i16, refill: Option<RowRefill>) {
+        let height = self.gfx.raw_height;
+        let count = core::cmp::min(rows.unsigned_abs(), height as u16) as i16;
+        if count == 0 {
+            return;
+        }
 
-        self.command(SSD1306_SETCONTRAST as u8);
-        self.command(
-            if self.gfx.raw_height == 32 {
-                0x8f
-            } else {
-                if vccstate == SSD1306_EXTERNALVCC { 0x9f } else { 0xcf }
+        // Moving up wraps the rows at the old start line to the bottom,
+        // moving down wraps the rows just above it to the top
+        let old_start = self.start_line as i16;
+        let new_start = (old_start + rows.rem_euclid(height)) % height;
+        let first = if rows > 0 { old_start } else { new_start };
+        self.set_start_line(new_start as u8);
+
+        if let Some(refill) = refill {
+            // The wrapped rows may go past the end of the RAM, refill them in two parts
+            let first_part = core::cmp::min(count, height - first);
+            refill(&mut self.gfx, first, first_part);
+            self.flush_rows(first, first_part);
+            if first_part < count {
+                refill(&mut self.gfx, 0, count - first_part);
+                self.flush_rows(0, count - first_part);
             }
-        );
+        }
+    }
 
-        self.command(SSD1306_SETPRECHARGE as u8);
-        self.command(if vccstate == SSD1306_EXTERNALVCC { 0x22 } else { 0xf1 });
+    fn fade_out(&mut self, interval: FadeInterval) {
+        self.send_command(Command::FadeBlink(FadeMode::FadeOut, interval));
+    }
 
-        self.command(SSD1306_SETVCOMDETECT as u8);
-        self.command(0x40);
+    fn blink(&mut self, interval: FadeInterval) {
+        self.send_command(Command::FadeBlink(FadeMode::Blink, interval));
+    }
 
-        self.command(SSD1306_DISPLAYALLON_RESUME as u8);
-        self.command(SSD1306_NORMALDISPLAY as u8);
-        self.command(SSD1306_DISPLAYON as u8);
+    fn fade_blink_off(&mut self) {
+        self.send_command(Command::FadeBlink(FadeMode::Disabled, FadeInterval::Frames8));
     }
 
-    fn invert_display(&mut self, i: bool) {
-        self.command(if i { SSD1306_INVERTDISPLAY as u8 } else { SSD1306_NORMALDISPLAY as u8 });
+    // Zoom needs the alternative COM pin configuration. Panels set up with sequential
+    // COM pins (128x32, 96x16) are switched to it while zoomed and restored afterwards
+    fn set_zoom(&mut self, zoom: bool) {
+        let left_right_remap = self.config.com_left_right_remap;
+        let com_pins = |alternative| Command::ComPins { alternative, left_right_remap };
+        if zoom {
+            if !self.config.com_alternative {
+                self.send_command(com_pins(true));
+            }
+            self.send_command(Command::ZoomIn(true));
+        } else {
+            self.send_command(Command::ZoomIn(false));
+            if !self.config.com_alternative {
+                self.send_command(com_pins(false));
+            }
+        }
+        self.gfx.set_zoom(zoom);
     }
 
-    // Send the display buffer out to the display
-    fn show(&mut self) {
-        self.command((SSD1306_SETLOWCOLUMN as u8) | 0x0); // Low column = 0
-        self.command((SSD1306_SETHIGHCOLUMN as u8) | 0x0); // High column = 0
-        self.command((SSD1306_SETSTARTLINE as u8) | 0x0); // Line #0
-        self.send_display_buffer();
+    fn sleep(&mut self) {
+        self.send_command(Command::DisplayOn(false));
+        if self.config.vcc == VccSource::Internal {
+            if let Some(command) = self.controller.supply_command(false) {
+                self.send_command(command);
+            }
+        }
+        self.power = PowerState::Sleep;
+    }
+
+    fn wake(&mut self) {
+        if self.config.vcc == VccSource::Internal {
+            if let Some(command) = self.controller.supply_command(true) {
+                self.send_command(command);
+            }
+        }
+        self.send_command(Command::DisplayOn(true));
+        // The charge pump needs ~100ms to settle
+        self.delay.delay_ms(100u32);
+
+        self.send_command(Command::Invert(self.inverted));
+        if let Some(config) = self.scroll {
+            self.start_scroll(config);
+        }
+        self.power = PowerState::On;
+        self.activity = true;
+    }
+
+    fn set_idle_timeout(&mut self, timeout: Option<ClockDuration>) {
+        self.idle_timeout = timeout;
+        self.activity = true;
+    }
+
+    fn poll_idle<C: Clock>(&mut self, clock: &mut C) -> bool {
+        let now = clock.now();
+        if self.activity || self.last_activity.is_none() {
+            self.last_activity = Some(now);
+            self.activity = false;
+        }
+
+        if let (Some(timeout), Some(last)) = (self.idle_timeout, self.last_activity) {
+            if self.power == PowerState::On && now >= last + timeout {
+                self.sleep();
+                self.power = PowerState::IdleSleep;
+            }
+        }
+        self.power != PowerState::On
     }
 
     // Clear the display buffer. Requires a display() call at some point afterwards
     fn clear_display(&mut self) {
         self.gfx.buffer.fill(0);
     }
-    //TODO: Don't forget to change this if you have 128x64 oLED
-    fn copy_adafruit_logo(&mut self, ada_fruit_logo: &[u8], raw_height: i16) {
-        // Determine the number of elements to copy
-        let length = if raw_height == 32 { ada_fruit_logo.len() / 2 } else { ada_fruit_logo.len() };
-
-        // Copy the elements
-        self.gfx.buffer[..length].copy_from_slice(&ada_fruit_logo[..length]);
+    // Copy a 128x64 logo into the buffer, scaled down to fit 32-row panels
+    fn copy_adafruit_logo(&mut self, ada_fruit_logo: &[u8]) {
+        Splash::new(SplashImage::new(ada_fruit_logo, 128, 64)).draw(&mut self.gfx);
     }
-    
-    //TODO: Don't forget to change this if you have 128x64 oLED
+
+    // Draw the builtin splash into the buffer. Does nothing without the `adafruit-splash` feature
     fn splash(&mut self) {
-        if !NO_SPLASH_ADAFRUIT {
-            let ada_fruit_logo: [u8; 1024] = [  //128*64
-                //128*32
-                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0x80, 0x80, 0x00, 0x00, 0x00, 0x00,
-                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0x80, 0xc0,
-                0xc0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0xc0, 0xe0, 0xf0, 0xf8, 0xfc,
-                0xf8, 0xe0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0x80, 0x80, 0x80, 0x80, 0x00, 0x80, 0x80, 0x00,
-                0x00, 0x00, 0x00, 0x80, 0x80, 0x80, 0x80, 0x80, 0x00, 0xff, 0xff, 0xff, 0x00, 0x00,
-                0x00, 0x00, 0x80, 0x80, 0x80, 0x80, 0x00, 0x00, 0x80, 0x80, 0x00, 0x00, 0x80, 0xff,
-                0xff, 0x80, 0x80, 0x00, 0x80, 0x80, 0x00, 0x80, 0x80, 0x80, 0x80, 0x00, 0x80, 0x80,
-                0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0x80, 0x00, 0x00, 0x8c, 0x8e, 0x84, 0x00, 0x00,
-                0x80, 0xf8, 0xf8, 0xf8, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                0x00, 0x00, 0x00, 0x00, 0xf0, 0xf0, 0xf0, 0xf0, 0xf0, 0xf0, 0xf0, 0xf0, 0xf0, 0xf0,
-                0xf0, 0xf0, 0xe0, 0xe0, 0xc0, 0x80, 0x00, 0xe0, 0xfc, 0xfe, 0xff, 0xff, 0xff, 0x7f,
-                0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                0x00, 0x00, 0x00, 0x00, 0x00, 0xfe, 0xff, 0xc7, 0x01, 0x01, 0x01, 0x01, 0x83, 0xff,
-                0xff, 0x00, 0x00, 0x7c, 0xfe, 0xc7, 0x01, 0x01, 0x01, 0x01, 0x83, 0xff, 0xff, 0xff,
-                0x00, 0x38, 0xfe, 0xc7, 0x83, 0x01, 0x01, 0x01, 0x83, 0xc7, 0xff, 0xff, 0x00, 0x00,
-                0x01, 0xff, 0xff, 0x01, 0x01, 0x00, 0xff, 0xff, 0x07, 0x01, 0x01, 0x01, 0x00, 0x00,
-                0x7f, 0xff, 0x80, 0x00, 0x00, 0x00, 0xff, 0xff, 0x7f, 0x00, 0x00, 0xff, 0xff, 0xff,
-                0x00, 0x00, 0x01, 0xff, 0xff, 0xff, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x0f, 0x3f, 0x7f, 0x7f, 0xff, 0xff, 0xff,
-                0xff, 0xff, 0xff, 0xff, 0xe7, 0xc7, 0xc7, 0x8f, 0x8f, 0x9f, 0xbf, 0xff, 0xff, 0xc3,
-                0xc0, 0xf0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc,
-                0xfc, 0xf8, 0xf8, 0xf0, 0xf0, 0xe0, 0xc0, 0x00, 0x01, 0x03, 0x03, 0x03, 0x03, 0x03,
-                0x01, 0x03, 0x03, 0x00, 0x00, 0x00, 0x00, 0x01, 0x03, 0x03, 0x03, 0x03, 0x01, 0x01,
-                0x03, 0x01, 0x00, 0x00, 0x00, 0x01, 0x03, 0x03, 0x03, 0x03, 0x01, 0x01, 0x03, 0x03,
-                0x00, 0x00, 0x00, 0x03, 0x03, 0x00, 0x00, 0x00, 0x03, 0x03, 0x00, 0x00, 0x00, 0x00,
-                0x00, 0x00, 0x00, 0x01, 0x03, 0x03, 0x03, 0x03, 0x03, 0x01, 0x00, 0x00, 0x00, 0x01,
-                0x03, 0x01, 0x00, 0x00, 0x00, 0x03, 0x03, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                //128x32^^^  128x64vvv
-                0x00, 0x00, 0x00, 0x80, 0xc0, 0xe0, 0xf0, 0xf9, 0xff, 0xff, 0xff, 0xff, 0xff, 0x3f,
-                0x1f, 0x0f, 0x87, 0xc7, 0xf7, 0xff, 0xff, 0x1f, 0x1f, 0x3d, 0xfc, 0xf8, 0xf8, 0xf8,
-                0xf8, 0x7c, 0x7d, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f, 0x3f, 0x0f,
-                0x07, 0x00, 0x30, 0x30, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xfe, 0xfe,
-                0xfc, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xe0, 0xc0, 0x00, 0x00, 0x00,
-                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x30, 0x30, 0x00, 0x00, 0x00, 0x00,
-                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                0x00, 0x00, 0x00, 0xc0, 0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
-                0x7f, 0x7f, 0x3f, 0x1f, 0x0f, 0x07, 0x1f, 0x7f, 0xff, 0xff, 0xf8, 0xf8, 0xff, 0xff,
-                0xff, 0xff, 0xff, 0xfe, 0xf8, 0xe0, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00,
-                0x00, 0x00, 0x00, 0x00, 0xfe, 0xfe, 0x00, 0x00, 0x00, 0xfc, 0xfe, 0xfc, 0x0c, 0x06,
-                0x06, 0x0e, 0xfc, 0xf8, 0x00, 0x00, 0xf0, 0xf8, 0x1c, 0x0e, 0x06, 0x06, 0x06, 0x0c,
-                0xff, 0xff, 0xff, 0x00, 0x00, 0xfe, 0xfe, 0x00, 0x00, 0x00, 0x00, 0xfc, 0xfe, 0xfc,
-                0x00, 0x18, 0x3c, 0x7e, 0x66, 0xe6, 0xce, 0x84, 0x00, 0x00, 0x06, 0xff, 0xff, 0x06,
-                0x06, 0xfc, 0xfe, 0xfc, 0x0c, 0x06, 0x06, 0x06, 0x00, 0x00, 0xfe, 0xfe, 0x00, 0x00,
-                0xc0, 0xf8, 0xfc, 0x4e, 0x46, 0x46, 0x46, 0x4e, 0x7c, 0x78, 0x40, 0x18, 0x3c, 0x76,
-                0xe6, 0xce, 0xcc, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x07, 0x0f, 0x1f,
-                0x1f, 0x3f, 0x3f, 0x3f, 0x3f, 0x1f, 0x0f, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0f, 0x0f, 0x00, 0x00, 0x00, 0x0f, 0x0f, 0x0f,
-                0x00, 0x00, 0x00, 0x00, 0x0f, 0x0f, 0x00, 0x00, 0x03, 0x07, 0x0e, 0x0c, 0x18, 0x18,
-                0x0c, 0x06, 0x0f, 0x0f, 0x0f, 0x00, 0x00, 0x01, 0x0f, 0x0e, 0x0c, 0x18, 0x0c, 0x0f,
-                0x07, 0x01, 0x00, 0x04, 0x0e, 0x0c, 0x18, 0x0c, 0x0f, 0x07, 0x00, 0x00, 0x00, 0x0f,
-                0x0f, 0x00, 0x00, 0x0f, 0x0f, 0x0f, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0f, 0x0f,
-                0x00, 0x00, 0x00, 0x07, 0x07, 0x0c, 0x0c, 0x18, 0x1c, 0x0c, 0x06, 0x06, 0x00, 0x04,
-                0x0e, 0x0c, 0x18, 0x0c, 0x0f, 0x07, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            ];
-            self.copy_adafruit_logo(&ada_fruit_logo, self.gfx.raw_height);
+        #[cfg(feature = "adafruit-splash")]
+        Splash::adafruit().draw(&mut self.gfx);
+    }
+
+    fn show_splash(&mut self, splash: &Splash) {
+        self.clear_display();
+        splash.draw(&mut self.gfx);
+        self.show();
+        if splash.fade_ms == 0 {
+            return;
         }
+
+        self.delay.delay_ms(splash.hold_ms);
+        let contrast = self.contrast;
+        for step in (0..SPLASH_FADE_STEPS).rev() {
+            self.send_command(Command::Contrast(((contrast as u32) * step / SPLASH_FADE_STEPS) as u8));
+            self.delay.delay_ms(splash.fade_ms / SPLASH_FADE_STEPS);
+        }
+
+        // Blank the panel before bringing the contrast back
+        self.clear_display();
+        self.show();
+        self.send_command(Command::Contrast(contrast));
     }
 
     fn command(&mut self, c: u8) {
@@ -328,6 +625,16 @@ impl<I2C, GPIO, DELAY, E> Display<I2C, GPIO, DELAY>
         let _ = self.i2c.write(self.address, &buff);
     }
 
+    fn send_command(&mut self, cmd: Command) {
+        write_command(&mut self.i2c, self.address, cmd);
+    }
+
+    fn send_commands(&mut self, cmds: &[Command]) {
+        for cmd in cmds {
+            self.send_command(*cmd);
+        }
+    }
+
     fn data(&mut self, c: u8) {
         let mut buff = [0; 2];
         buff[0] = 0x40; // Data Mode
@@ -336,87 +643,284 @@ impl<I2C, GPIO, DELAY, E> Display<I2C, GPIO, DELAY>
     }
 
     fn send_display_buffer(&mut self) {
-        let mut buff = [0; 17];
-        buff[0] = 0x40; // Data Mode
-        let len = self.gfx.buffer.len();
-        // Send display buffer in 16-byte chunks
-        let mut i = 0;
-        while i < len {
-            for x in 1..16 {
-                buff[x] = self.gfx.buffer[i + x - 1];
-            }
-            let _e = self.i2c.write(self.address, &buff); // Send only the filled part
-            i += 16;
-        }
+        let last_column = (self.gfx.raw_width - 1) as u8;
+        let last_page = (self.gfx.raw_height / 8 - 1) as u8;
+        self.flush_area(0, last_column, 0, last_page);
     }
 
     fn activate_scroll(&mut self) {
-        self.command(SSD1306_ACTIVATE_SCROLL as u8);
+        self.send_command(Command::ActivateScroll);
     }
     fn deactivate_scroll(&mut self) {
-        self.command(SSD1306_DEACTIVATE_SCROLL as u8);
+        self.scroll = None;
+        self.send_commands(&[
+            Command::DeactivateScroll,
+            // Back to the reset value, so a vertical area does not outlive its scroll
+            Command::VerticalScrollArea { fixed_rows: 0, scroll_rows: self.gfx.raw_height as u8 },
+        ]);
+    }
+    fn scroll(&mut self, config: &ScrollConfig) {
+        self.start_scroll(*config);
     }
+
+    fn set_vertical_scroll_area(&mut self, fixed_rows: u8, scroll_rows: u8) {
+        self.send_command(Command::VerticalScrollArea { fixed_rows, scroll_rows });
+    }
+
     fn scroll_horizontal_r(&mut self) {
-        self.deactivate_scroll();
-        self.command(SSD1306_HORIZONTAL_SCROLL_R as u8); // 0x26
-        self.command(0x0);    // Dummy byte
-        self.command(0x0);    // Start from page 0
-        self.command(0x0);    // Time Interval
-        self.command(0x7);    // Last Page
-        self.command(0x0);    // dummy
-        self.command(0xff);    // dummy
-        self.activate_scroll(); // Activate scrolling
+        self.scroll(&ScrollConfig::horizontal(ScrollDirection::Right));
     }
 
     fn scroll_horizontal_l(&mut self) {
-        self.deactivate_scroll();
-        self.command(SSD1306_HORIZONTAL_SCROLL_L as u8); // 0x27
-        self.command(0x0);    // Dummy byte
-        self.command(0x0);    // Start from page 7 (if scrolling left)
-        self.command(0x0);    // Time Interval
-        self.command(0x7);    // Last Page (consider your display's configuration)
-        self.command(0x0);    // dummy
-        self.command(0xff);    // dummy
-        self.activate_scroll(); // Activate scrolling
+        self.scroll(&ScrollConfig::horizontal(ScrollDirection::Left));
     }
-    fn scroll_diagnol_r(&mut self) {
-        self.deactivate_scroll();
 
-            // Set up the vertical scrolling
-        self.command(SSD1306_VERTICAL_R_SCROLL as u8); // Command for vertical scroll up
-        self.command(0x0);    // Dummy byte
-        self.command(0x0);    // First Page (starting from page 0)
-        self.command(0x0);    // Time Interval (5 frames/sec)
-        self.command(0x7);    // Last Page (0x7 for an 8-page display)
-        self.command(0x1);    // Vertical scrolling offset (1 row)
-    self.activate_scroll(); // Activate scrolling
+    fn scroll_diagnol_r(&mut self) {
+        self.scroll(&ScrollConfig::diagonal(ScrollDirection::Right, 1));
     }
-    
+
     fn scroll_diagnol_l(&mut self) {
-        self.deactivate_scroll();
-        
+        self.scroll(&ScrollConfig::diagonal(ScrollDirection::Left, 1));
+    }
 
-        // Now set up the vertical scrolling down
-        self.command(SSD1306_VERTICAL_L_SCROLL as u8); // Command for vertical scroll down
-        self.command(0x0);    // Dummy
-        self.command(0x0);    // First Page (starting from page 0)
-        self.command(0x0);    // Time Interval (5 frames/sec)
-        self.command(0x7);    // Last Page (0x7 for an 8-page display)
-        self.command(0x1);    // Vertical scrolling offset (1 row)
-        self.activate_scroll(); // Activate scrolling
+    // The controller has no pure vertical scroll: this is a diagonal scroll where
+    // only page 7 moves sideways while the vertical offset moves the whole screen
+    fn scroll_vertical_u(&mut self) {
+        self.scroll(&ScrollConfig::diagonal(ScrollDirection::Right, 1).pages(7, 7));
     }
 
- fn scroll_vertical_u(&mut self) {
-    self.deactivate_scroll();
-    
-    self.command(SSD1306_VERTICAL_R_SCROLL as u8);
-    self.command(0x0);    // Dummy byte
-    self.command(0x7);    // First Page
-    self.command(0x0);    // Time Interval
-    self.command(0x7);    // Last Page
-    self.command(0x1);    // Vertical offset
-
-    self.activate_scroll();
 }
 
+impl<I2C, GPIO, DELAY, E> AdafruitSSD1306<I2C, GPIO, DELAY>
+    where
+        DELAY: DelayNs,
+        GPIO: MyGpioPin,
+        I2C: I2c<Error = E>
+{
+    /// Exchange the back buffer (gfx.buffer) with the front one. The back buffer then
+    /// holds an older frame: clear and redraw it completely
+    #[cfg(feature = "double-buffer")]
+    pub fn swap_buffers(&mut self) {
+        core::mem::swap(&mut self.gfx.buffer, &mut self.front);
+    }
+
+    /// One bit per page (bit 0 = page 0) whose bytes differ between the back and front buffers
+    #[cfg(feature = "double-buffer")]
+    pub fn dirty_pages(&self) -> u8 {
+        let width = self.gfx.raw_width as usize;
+        let mut mask = 0;
+        for page in 0..(self.gfx.raw_height / 8) as usize {
+            let range = page * width..(page + 1) * width;
+            if self.gfx.buffer[range.clone()] != self.front[range] {
+                mask |= 1 << page;
+            }
+        }
+        mask
+    }
+
+    /// Send the pages whose bit is set in `pages` (bit 0 = page 0).
+    /// Runs of consecutive pages go out as one area
+    pub fn show_pages(&mut self, pages: u8) {
+        self.activity = true;
+        if self.power == PowerState::IdleSleep {
+            self.wake();
+        }
+        let last_column = (self.gfx.raw_width - 1) as u8;
+        let count = (self.gfx.raw_height / 8) as u8;
+        for (first, last) in page_runs(pages, count) {
+            self.flush_area(0, last_column, first, last);
+        }
+    }
+
+    /// Prepare the panel for a whole frame sent as one data transfer, see `FrameFlusher`.
+    /// Needs horizontal addressing, the Config default, so not the SH1106
+    pub fn set_frame_window(&mut self) {
+        self.activity = true;
+        if self.power == PowerState::IdleSleep {
+            self.wake();
+        }
+        self.send_command(Command::StartLine(self.start_line));
+        let window = FlushWindow::full(self.controller, &self.config, self.gfx.raw_width, self.gfx.raw_height);
+        if let Some(commands) = window.window_commands() {
+            self.send_commands(&commands);
+        }
+    }
+
+    /// Look for the display on both addresses and use the one that acknowledges.
+    /// The controller must be out of reset (rst high), call begin() again if the address changed
+    pub fn probe(&mut self) -> Result<u8, AdafruitSSD1306Error<E>> {
+        self.address = probe(&mut self.i2c)?;
+        Ok(self.address)
+    }
+
+    // Send the pages holding raw rows first..first + count to the panel
+    fn flush_rows(&mut self, first: i16, count: i16) {
+        let last_column = (self.gfx.raw_width - 1) as u8;
+        self.flush_area(0, last_column, (first / 8) as u8, ((first + count - 1) / 8) as u8);
+    }
+
+    // Send raw columns first_column..=last_column of pages first_page..=last_page to the panel.
+    // Page addressing needs the start column and page before every page, the other
+    // controllers take a single window
+    fn flush_area(&mut self, first_column: u8, last_column: u8, first_page: u8, last_page: u8) {
+        let window = FlushWindow::new(self.controller, &self.config, first_column, last_column, first_page, last_page);
+        flush_window(&mut self.i2c, self.address, window, self.gfx.raw_width, &self.gfx.buffer);
+        // The panel now differs from the front buffer
+        #[cfg(feature = "double-buffer")]
+        {
+            self.front_shown = false;
+        }
+    }
+
+    // Replace the running scroll with `config` and remember it for wake()
+    fn start_scroll(&mut self, config: ScrollConfig) {
+        // Keeps an area set with set_vertical_scroll_area() for a config without one
+        self.send_command(Command::DeactivateScroll);
+        if let Some(area) = config.area_command() {
+            self.send_command(area);
+        }
+        self.send_command(config.setup_command());
+        self.activate_scroll();
+        self.scroll = Some(config);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "double-buffer")]
+    use crate::adafruit_gfx_h::{ Drawable, WHITE };
+    use crate::mock;
+
+    #[test]
+    fn show_sends_the_whole_buffer() {
+        let mut display = mock::display(128, 64, Controller::Ssd1306);
+        for (i, byte) in display.gfx.buffer.iter_mut().enumerate() {
+            *byte = i as u8 | 1;
+        }
+        display.show();
+        assert_eq!(display.i2c.data(0x3c), display.gfx.buffer[..1024]);
+    }
+
+    #[test]
+    fn two_displays_share_one_bus() {
+        use core::cell::RefCell;
+        use embedded_hal_bus::i2c::RefCellDevice;
+        let bus = RefCell::new(mock::MockI2c::new(&[SSD1306_I2C_ADDRESS, SSD1306_I2C_ADDRESS_ALT]));
+        let mut left = AdafruitSSD1306::new(RefCellDevice::new(&bus), SSD1306_I2C_ADDRESS, mock::MockPin::default(), mock::NoDelay, AdafruitGFX::new(128, 64), Controller::Ssd1306);
+        let mut right = AdafruitSSD1306::new(RefCellDevice::new(&bus), SSD1306_I2C_ADDRESS_ALT, mock::MockPin::default(), mock::NoDelay, AdafruitGFX::new(128, 64), Controller::Ssd1306);
+        left.gfx.buffer.fill(0x0f);
+        right.gfx.buffer.fill(0xf0);
+        left.show();
+        right.show();
+        let bus = bus.borrow();
+        assert_eq!(bus.data(SSD1306_I2C_ADDRESS), left.gfx.buffer[..1024]);
+        assert_eq!(bus.data(SSD1306_I2C_ADDRESS_ALT), right.gfx.buffer[..1024]);
+        assert_eq!(bus.commands(SSD1306_I2C_ADDRESS), bus.commands(SSD1306_I2C_ADDRESS_ALT));
+    }
+
+    #[test]
+    fn fade_and_blink_commands() {
+        let mut display = mock::display(128, 64, Controller::Ssd1306);
+        display.fade_out(FadeInterval::Frames40);
+        display.blink(FadeInterval::Frames128);
+        display.fade_blink_off();
+        assert_eq!(display.i2c.writes, [
+            (0x3c, [0x00, 0x23, 0x20 | 4].to_vec()),
+            (0x3c, [0x00, 0x23, 0x30 | 15].to_vec()),
+            (0x3c, [0x00, 0x23, 0x00].to_vec()),
+        ]);
+    }
+
+    #[test]
+    fn deactivate_scroll_resets_the_vertical_area() {
+        let mut display = mock::display(128, 64, Controller::Ssd1306);
+        display.scroll(&ScrollConfig::diagonal(ScrollDirection::Right, 1).vertical_area(16, 48));
+        display.deactivate_scroll();
+        assert_eq!(display.i2c.commands(0x3c), [
+            [0x2e].to_vec(),
+            [0xa3, 16, 48].to_vec(),
+            [0x29, 0x00, 0x00, 0x00, 0x07, 0x01].to_vec(),
+            [0x2f].to_vec(),
+            [0x2e].to_vec(),
+            [0xa3, 0, 64].to_vec(),
+        ]);
+    }
+
+    #[test]
+    fn soft_scroll_moves_the_start_line() {
+        let mut display = mock::display(128, 64, Controller::Ssd1306);
+        display.scroll_vertical_soft(8, None);
+        display.scroll_vertical_soft(-16, None);
+        assert_eq!(display.start_line, 56);
+        assert_eq!(display.i2c.commands(0x3c), [[0x48], [0x78]]);
+    }
+
+    #[test]
+    fn soft_scroll_handles_extreme_rows() {
+        let mut display = mock::display(128, 64, Controller::Ssd1306);
+        display.set_start_line(60);
+        for rows in [i16::MIN, i16::MAX, i16::MIN + 1] {
+            let mut refilled = 0;
+            display.scroll_vertical_soft(rows, Some(&mut |_, _, count| refilled += count));
+            assert_eq!(refilled, 64);
+        }
+        // The three moves add up to -32768, a multiple of 64
+        assert_eq!(display.start_line, 60);
+    }
+
+    #[cfg(feature = "double-buffer")]
+    #[test]
+    fn show_changed_sends_every_page_first() {
+        let mut display = mock::display(128, 64, Controller::Ssd1306);
+        display.show_changed();
+        assert_eq!(display.i2c.data(0x3c).len(), 1024);
+    }
+
+    #[cfg(feature = "double-buffer")]
+    #[test]
+    fn show_changed_sends_changed_pages_from_the_front_buffer() {
+        let mut display = mock::display(128, 64, Controller::Ssd1306);
+        display.show_changed();
+        display.i2c.writes.clear();
+
+        display.gfx.buffer.fill(0);
+        display.gfx.draw_pixel(5, 20, WHITE);
+        display.show_changed();
+        assert_eq!(display.i2c.commands(0x3c), [[0x21, 0, 127], [0x22, 2, 2]]);
+        let data = display.i2c.data(0x3c);
+        assert_eq!((data.len(), data[5]), (128, 0x10));
+        // The new frame is in front, the back buffer holds the previous one
+        assert_eq!(display.front[2 * 128 + 5], 0x10);
+        assert_eq!(display.gfx.buffer[2 * 128 + 5], 0x00);
+    }
+
+    #[cfg(feature = "double-buffer")]
+    #[test]
+    fn partial_flush_makes_show_changed_send_everything() {
+        let mut display = mock::display(128, 64, Controller::Ssd1306);
+        display.show();
+        display.show_pages(0x01);
+        display.i2c.writes.clear();
+        display.show_changed();
+        assert_eq!(display.i2c.data(0x3c).len(), 1024);
+    }
+
+    #[test]
+    fn zoom_on_64_rows_keeps_the_com_pins() {
+        let mut display = mock::display(128, 64, Controller::Ssd1306);
+        display.set_zoom(true);
+        display.set_zoom(false);
+        assert_eq!(display.i2c.commands(0x3c), [[0xd6, 0x01], [0xd6, 0x00]]);
+    }
+
+    #[test]
+    fn zoom_on_32_rows_switches_to_alternative_com_pins() {
+        let mut display = mock::display(128, 32, Controller::Ssd1306);
+        display.set_zoom(true);
+        assert_eq!(display.gfx.height, 16);
+        display.set_zoom(false);
+        assert_eq!(display.gfx.height, 32);
+        assert_eq!(display.i2c.commands(0x3c), [[0xda, 0x12], [0xd6, 0x01], [0xd6, 0x00], [0xda, 0x02]]);
+    }
 }
@@ -0,0 +1,191 @@
+/*
+ *  Async variant of the SSD1306 driver for Embassy/RTIC, enabled by the `async` feature.
+ *  Every I2C transfer is awaited, so a full flush (~100 ms at 100 kHz) yields to the
+ *  executor between pages instead of blocking it.
+ *  The init sequence, probe and FlushWindow addressing are shared with the blocking AdafruitSSD1306.
+ */
+
+use crate::adafruit_gfx_h::AdafruitGFX;
+use crate::adafruit_ssd1306::{ AdafruitSSD1306Error, MyGpioPin, PROBE_ADDRESSES, PROBE_WRITE };
+use crate::config::Config;
+use crate::controller::Controller;
+use crate::ssd1306_command::Command;
+use crate::window::FlushWindow;
+use embedded_hal_async::{ delay::DelayNs, i2c::I2c };
+
+/// Largest data transfer, one page of a 128 column panel
+const MAX_DATA_CHUNK: usize = 128;
+
+pub struct AdafruitSSD1306Async<I2C, GPIO, DELAY> {
+    pub rst: GPIO,
+    pub i2c: I2C,
+    pub address: u8,
+    pub delay: DELAY,
+    pub gfx: AdafruitGFX,
+    pub controller: Controller,
+    pub config: Config,     // settings sent by begin()
+    pub start_line: u8,     // RAM row shown on the top panel row
+}
+
+impl<I2C, GPIO, DELAY, E> AdafruitSSD1306Async<I2C, GPIO, DELAY>
+    where
+        DELAY: DelayNs,
+        GPIO: MyGpioPin,
+        I2C: I2c<Error = E>
+{
+    pub fn new(ni2c: I2C, address: u8, n_rst: GPIO, delay: DELAY, ngfx: AdafruitGFX, controller: Controller) -> Self {
+        AdafruitSSD1306Async {
+            rst: n_rst,
+            i2c: ni2c,
+            address,
+            delay,
+            controller,
+            config: Config::for_panel(ngfx.raw_width, ngfx.raw_height),
+            start_line: 0,
+            gfx: ngfx,
+        }
+    }
+
+    /// Same as `adafruit_ssd1306::probe()`, trying 0x3C then 0x3D.
+    /// The controller must be out of reset (rst high), call begin() again if the address changed
+    pub async fn probe(&mut self) -> Result<u8, AdafruitSSD1306Error<E>> {
+        for address in PROBE_ADDRESSES {
+            if self.i2c.write(address, &PROBE_WRITE).await.is_ok() {
+                self.address = address;
+                return Ok(address);
+            }
+        }
+        Err(AdafruitSSD1306Error::NoDevice)
+    }
+
+    /// Reset and initialise the controller, see `Config::for_panel()` for presets
+    pub async fn begin(&mut self, config: &Config) -> Result<(), AdafruitSSD1306Error<E>> {
+        let _ = self.rst.set_high();
+        self.delay.delay_ms(1).await;
+        let _ = self.rst.set_low();
+        self.delay.delay_ms(10).await;
+        let _ = self.rst.set_high();
+
+        self.config = *config;
+        self.start_line = 0;
+        self.send_commands(&config.init_commands(self.controller)).await
+    }
+
+    pub fn clear_display(&mut self) {
+        self.gfx.buffer.fill(0);
+    }
+
+    pub async fn invert_display(&mut self, i: bool) -> Result<(), AdafruitSSD1306Error<E>> {
+        self.send_command(Command::Invert(i)).await
+    }
+
+    pub async fn set_contrast(&mut self, contrast: u8) -> Result<(), AdafruitSSD1306Error<E>> {
+        self.config.contrast = contrast;
+        self.send_command(Command::Contrast(contrast)).await
+    }
+
+    pub async fn set_start_line(&mut self, line: u8) -> Result<(), AdafruitSSD1306Error<E>> {
+        self.start_line = line % (self.gfx.raw_height as u8);
+        self.send_command(Command::StartLine(self.start_line)).await
+    }
+
+    /// Send the display buffer out to the display, one page per transfer
+    pub async fn show(&mut self) -> Result<(), AdafruitSSD1306Error<E>> {
+        self.send_command(Command::StartLine(self.start_line)).await?;
+        let window = FlushWindow::full(self.controller, &self.config, self.gfx.raw_width, self.gfx.raw_height);
+        if let Some(commands) = window.window_commands() {
+            self.send_commands(&commands).await?;
+        }
+        for page in window.pages() {
+            if let Some(commands) = window.page_commands(page) {
+                self.send_commands(&commands).await?;
+            }
+            let range = window.buffer_range(page, self.gfx.raw_width);
+            self.send_buffer_range(range.start, range.end).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn send_command(&mut self, cmd: Command) -> Result<(), AdafruitSSD1306Error<E>> {
+        let bytes = cmd.bytes();
+        let bytes = bytes.as_slice();
+        let mut buff = [0; 8];
+        buff[0] = 0; // Command Mode, the whole command in one transfer
+        buff[1..=bytes.len()].copy_from_slice(bytes);
+        self.i2c.write(self.address, &buff[..=bytes.len()]).await.map_err(AdafruitSSD1306Error::I2cError)
+    }
+
+    pub async fn send_commands(&mut self, cmds: &[Command]) -> Result<(), AdafruitSSD1306Error<E>> {
+        for cmd in cmds {
+            self.send_command(*cmd).await?;
+        }
+        Ok(())
+    }
+
+    // Send buffer[start..end] as data, at most MAX_DATA_CHUNK bytes per transfer
+    async fn send_buffer_range(&mut self, start: usize, end: usize) -> Result<(), AdafruitSSD1306Error<E>> {
+        let mut buff = [0; MAX_DATA_CHUNK + 1];
+        buff[0] = 0x40; // Data Mode
+        let mut i = start;
+        while i < end {
+            let n = core::cmp::min(MAX_DATA_CHUNK, end - i);
+            buff[1..=n].copy_from_slice(&self.gfx.buffer[i..i + n]);
+            self.i2c.write(self.address, &buff[..=n]).await.map_err(AdafruitSSD1306Error::I2cError)?;
+            i += n;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adafruit_ssd1306_h::SSD1306_I2C_ADDRESS_ALT;
+    use crate::mock::{ block_on, MockI2c, MockPin, NoDelay };
+
+    fn display(controller: Controller) -> AdafruitSSD1306Async<MockI2c, MockPin, NoDelay> {
+        let i2c = MockI2c::new(&[SSD1306_I2C_ADDRESS_ALT]);
+        AdafruitSSD1306Async::new(i2c, SSD1306_I2C_ADDRESS_ALT, MockPin::default(), NoDelay, AdafruitGFX::new(128, 64), controller)
+    }
+
+    #[test]
+    fn begin_sends_the_init_sequence() {
+        let mut display = display(Controller::Ssd1306);
+        let config = Config::for_panel(128, 64);
+        block_on(display.begin(&config)).unwrap();
+        assert!(display.rst.high);
+        let expected: [_; 19] = config.init_commands(Controller::Ssd1306).map(|cmd| cmd.bytes().as_slice().to_vec());
+        assert_eq!(display.i2c.commands(0x3d), expected);
+    }
+
+    #[test]
+    fn show_sends_the_window_and_every_page() {
+        let mut display = display(Controller::Ssd1306);
+        display.gfx.buffer[0] = 0x81;
+        display.gfx.buffer[1023] = 0x18;
+        block_on(display.show()).unwrap();
+        assert_eq!(display.i2c.commands(0x3d), [[0x40].to_vec(), [0x21, 0, 127].to_vec(), [0x22, 0, 7].to_vec()]);
+        let data = display.i2c.data(0x3d);
+        assert_eq!(data.len(), 1024);
+        assert_eq!((data[0], data[1023]), (0x81, 0x18));
+        assert!(display.i2c.writes.iter().all(|(_, bytes)| bytes.len() <= MAX_DATA_CHUNK + 1));
+    }
+
+    #[test]
+    fn show_on_sh1106_uses_page_addressing() {
+        let mut display = display(Controller::Sh1106);
+        block_on(display.show()).unwrap();
+        let commands = display.i2c.commands(0x3d);
+        assert_eq!(commands.len(), 1 + 8 * 2);
+        assert_eq!(commands[1..3], [[0xb0].to_vec(), [0x02, 0x10].to_vec()]);
+        assert_eq!(commands[15..], [[0xb7].to_vec(), [0x02, 0x10].to_vec()]);
+    }
+
+    #[test]
+    fn probe_finds_the_alternative_address() {
+        let mut display = display(Controller::Ssd1306);
+        display.address = 0;
+        assert_eq!(block_on(display.probe()).unwrap(), 0x3d);
+        assert_eq!(display.address, 0x3d);
+    }
+}
@@ -27,11 +27,18 @@ All text above, and the splash screen must be included in any redistribution
 
 
 
-use crate::adafruit_gfx_h::{ AdafruitGFX };
+use crate::adafruit_gfx_h::{ AdafruitGFX, Rect };
+use crate::clock::{ Clock, ClockDuration, ClockInstant };
+use crate::config::{ Config, Orientation };
+use crate::controller::Controller;
+use crate::scroll::ScrollConfig;
+use crate::splash::Splash;
+use crate::ssd1306_command::{ Command, FadeInterval };
 
 pub const SSD1306_EXTERNALVCC:u8 = 0x0;
 pub const SSD1306_SWITCHCAPVCC:u8 = 0x1;
-use embedded_hal::delay::DelayNs;
+pub const SSD1306_I2C_ADDRESS:u8 = 0x3c;       // SA0 tied low
+pub const SSD1306_I2C_ADDRESS_ALT:u8 = 0x3d;   // SA0 tied high
 
 
 /** The pure base class for the SSD1306 display driver.
@@ -40,30 +47,100 @@ use embedded_hal::delay::DelayNs;
  * such as the SPI and I2C drivers.
  */
 
-pub struct AdafruitSSD1306<I2C, GPIO, DELAY> 
-   where DELAY: DelayNs {
+pub struct AdafruitSSD1306<I2C, GPIO, DELAY> {
     pub rst: GPIO,
     pub i2c: I2C,
     pub address: u8,
     pub delay: DELAY,
     pub gfx : AdafruitGFX,
+    pub controller: Controller,
+    pub contrast: u8,       // contrast last sent to the panel
+    pub config: Config,     // settings sent by begin(), precharge and VCOMH restored at normal brightness
+    pub inverted: bool,
+    pub scroll: Option<ScrollConfig>, // active scroll, restored on wake
+    pub power: PowerState,
+    pub idle_timeout: Option<ClockDuration>,
+    pub last_activity: Option<ClockInstant>,
+    pub activity: bool,     // show() was called since the last poll_idle()
+    pub start_line: u8,     // RAM row shown on the top panel row
+    #[cfg(feature = "double-buffer")]
+    pub front: [u8; 1024],  // frame last sent to the panel, gfx.buffer is the back buffer drawn into
+    #[cfg(feature = "double-buffer")]
+    pub front_shown: bool,  // the panel shows the front buffer, show_changed() may skip unchanged pages
+}
+
+/// Redraws `count` raw rows starting at `first_row` after a software vertical scroll
+pub type RowRefill<'a> = &'a mut dyn FnMut(&mut AdafruitGFX, i16, i16);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PowerState {
+    On,
+    Sleep,      // put to sleep by sleep()
+    IdleSleep,  // put to sleep by poll_idle(), woken by the next show()
 }
 
 pub trait Display<I2C, GPIO, DELAY> {
-    fn new(ni2c: I2C, n_rst: GPIO, delay: DELAY, ngfx:AdafruitGFX) -> Self;
-    fn begin(&mut self, vccstate: u8);
+    /// `address` is SSD1306_I2C_ADDRESS or SSD1306_I2C_ADDRESS_ALT, see `probe()` when unknown
+    fn new(ni2c: I2C, address: u8, n_rst: GPIO, delay: DELAY, ngfx:AdafruitGFX, controller: Controller) -> Self;
+    /// Reset and initialise the controller, see `Config::for_panel()` for presets
+    fn begin(&mut self, config: &Config);
     fn clear_display(&mut self);
     fn invert_display(&mut self, i: bool);
+    /// Mirror the panel in hardware at runtime (begin() uses `Config::orientation`).
+    /// Call show() afterwards since the segment remap only applies to data written after it
+    fn set_orientation(&mut self, orientation: Orientation);
+    /// Set the panel contrast, 0-255
+    fn set_contrast(&mut self, contrast: u8);
+    /// Drop the contrast to 0, or restore the contrast set before
+    fn dim(&mut self, dim: bool);
+    /// Set a perceptual brightness, 0-255. Very low levels also reduce precharge and VCOMH
+    fn set_brightness(&mut self, level: u8);
     fn show(&mut self);
+    /// Swap the buffers and send the pages of the new front buffer that differ from the
+    /// frame on the panel. Sends every page the first time after begin() or a partial flush
+    #[cfg(feature = "double-buffer")]
+    fn show_changed(&mut self);
+    /// Send only the pages and columns covering `region` (display coordinates)
+    fn show_region(&mut self, region: Rect);
+    /// Let the controller fade the panel out, one contrast step every `interval`
+    fn fade_out(&mut self, interval: FadeInterval);
+    /// Let the controller fade out and back in continuously, one contrast step every `interval`
+    fn blink(&mut self, interval: FadeInterval);
+    /// Stop fade out / blink and return to the normal contrast
+    fn fade_blink_off(&mut self);
+    /// Hardware zoom-in: every row is shown twice, drawing is limited to the top half.
+    /// Sets the alternative COM pin configuration zoom needs while zoomed
+    fn set_zoom(&mut self, zoom: bool);
+    /// Turn the panel and its charge pump off. RAM content is kept
+    fn sleep(&mut self);
+    /// Turn the panel back on and restore invert and scroll state
+    fn wake(&mut self);
+    /// Blank the panel after `timeout` without show() calls, None disables it
+    fn set_idle_timeout(&mut self, timeout: Option<ClockDuration>);
+    /// Check the idle timeout against `clock`, returns true while the panel sleeps
+    fn poll_idle<C: Clock>(&mut self, clock: &mut C) -> bool;
     fn splash(&mut self);
+    /// Draw and show a splash screen, then hold and fade it out if the splash asks for it
+    fn show_splash(&mut self, splash: &Splash);
     fn send_display_buffer(&mut self);
     // Transport methods
     fn command(&mut self, c: u8);
+    /// Send one typed command with its arguments
+    fn send_command(&mut self, cmd: Command);
+    /// Send a sequence of typed commands
+    fn send_commands(&mut self, cmds: &[Command]);
     fn data(&mut self, c: u8);
-    fn copy_adafruit_logo(&mut self, ada_fruit_logo: &[u8], raw_height: i16);
+    fn copy_adafruit_logo(&mut self, ada_fruit_logo: &[u8]);
     fn activate_scroll(&mut self);
+    /// Stop the hardware scroll and reset the vertical scroll area to the whole panel
     fn deactivate_scroll(&mut self);
 
+    /// Start a continuous hardware scroll described by `config`
+    fn scroll(&mut self, config: &ScrollConfig);
+    /// Rows fixed at the top and rows moved by a diagonal scroll (command 0xA3),
+    /// kept until deactivate_scroll()
+    fn set_vertical_scroll_area(&mut self, fixed_rows: u8, scroll_rows: u8);
+
     fn scroll_horizontal_r(&mut self);
     fn scroll_horizontal_l(&mut self);
     
@@ -71,4 +148,11 @@ pub trait Display<I2C, GPIO, DELAY> {
     fn scroll_diagnol_l(&mut self);
 
     fn scroll_vertical_u(&mut self);
+
+    /// Show RAM row `line` on the top panel row (command 0x40-0x7F)
+    fn set_start_line(&mut self, line: u8);
+    /// Software vertical scroll by moving the start line, positive `rows` moves the content up.
+    /// `refill(gfx, first_row, count)` may redraw the raw rows that wrapped around,
+    /// only their pages are sent to the panel
+    fn scroll_vertical_soft(&mut self, rows: i16, refill: Option<RowRefill>);
 }
\ No newline at end of file
@@ -0,0 +1,14 @@
+/*
+ *  Time source used by the driver for anything that happens over time
+ *  (idle sleep, animations, ...). Same 1 MHz tick as the example's time::Ticker.
+ */
+
+use fugit::{ Duration, Instant };
+
+pub type ClockInstant = Instant<u64, 1, 1_000_000>;
+pub type ClockDuration = Duration<u64, 1, 1_000_000>;
+
+/// A monotonic clock supplied by the application
+pub trait Clock {
+    fn now(&mut self) -> ClockInstant;
+}
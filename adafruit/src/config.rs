@@ -0,0 +1,230 @@
+/*
+ *  Panel configuration used by begin().
+ */
+
+use crate::controller::Controller;
+use crate::ssd1306_command::{ AddressingMode, Command, VcomhLevel };
+
+/// How the panel is mounted, handled by the controller instead of AdafruitGFX rotation
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Orientation {
+    pub flip_horizontal: bool,      // mirror left and right (segment remap)
+    pub flip_vertical: bool,        // mirror top and bottom (COM scan direction)
+}
+
+impl Orientation {
+    /// Module mounted as designed
+    pub const NORMAL: Orientation = Orientation { flip_horizontal: false, flip_vertical: false };
+    /// Module mounted upside down
+    pub const ROTATED_180: Orientation = Orientation { flip_horizontal: true, flip_vertical: true };
+
+    /// Segment remap and COM scan direction commands for this orientation
+    pub fn commands(&self) -> [Command; 2] {
+        [
+            Command::SegmentRemap(!self.flip_horizontal),
+            Command::ComScanReverse(!self.flip_vertical),
+        ]
+    }
+}
+
+/// Where the panel's high voltage comes from
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VccSource {
+    Internal,   // internal charge pump (SSD1306_SWITCHCAPVCC)
+    External,   // external supply on VCC (SSD1306_EXTERNALVCC)
+}
+
+/// Everything begin() sends to the controller.
+/// Start from a preset with `Config::for_panel()` and adjust with the builder methods
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Config {
+    pub vcc: VccSource,
+    pub clock_divide: u8,           // 1-16
+    pub oscillator: u8,             // 0-15, higher is faster
+    pub display_offset: u8,         // vertical shift of the COM lines
+    pub com_alternative: bool,      // alternative COM pin configuration, used by most 64-row panels
+    pub com_left_right_remap: bool,
+    pub multiplex: u8,              // active rows, 16-64
+    pub precharge: (u8, u8),        // phase 1 and 2, in DCLKs
+    pub vcomh: VcomhLevel,
+    pub addressing_mode: AddressingMode,
+    pub column_offset: u8,          // first RAM column wired to the panel, on top of the controller's own offset
+    pub contrast: u8,
+    pub orientation: Orientation,
+}
+
+impl Config {
+    /// Preset for a known panel size: 128x64, 128x32, 96x16, 64x48, 72x40 or 64x32.
+    /// Other sizes get the 128x64 settings with the multiplex set to `height`
+    pub fn for_panel(width: i16, height: i16) -> Self {
+        let base = Config {
+            vcc: VccSource::Internal,
+            clock_divide: 1,
+            oscillator: 8,
+            display_offset: 0,
+            com_alternative: true,
+            com_left_right_remap: false,
+            multiplex: height as u8,
+            precharge: (1, 15),
+            vcomh: VcomhLevel::Raw(0x40),
+            addressing_mode: AddressingMode::Horizontal,
+            column_offset: 0,
+            contrast: 0xcf,
+            orientation: Orientation::NORMAL,
+        };
+
+        match (width, height) {
+            (128, 32) => Config { com_alternative: false, contrast: 0x8f, ..base },
+            (96, 16) => Config { com_alternative: false, contrast: 0xaf, ..base },
+            (64, 48) => Config { column_offset: 32, ..base },
+            (72, 40) => Config { column_offset: 28, contrast: 0x82, ..base },
+            (64, 32) => Config { column_offset: 32, ..base },
+            _ => base,
+        }
+    }
+
+    /// Also switches precharge and contrast to the external supply defaults
+    pub fn vcc(mut self, vcc: VccSource) -> Self {
+        self.vcc = vcc;
+        match vcc {
+            VccSource::External => {
+                self.precharge = (2, 2);
+                if self.multiplex > 32 {
+                    self.contrast = 0x9f;
+                }
+            }
+            VccSource::Internal => self.precharge = (1, 15),
+        }
+        self
+    }
+
+    pub fn clock(mut self, divide: u8, oscillator: u8) -> Self {
+        self.clock_divide = divide;
+        self.oscillator = oscillator;
+        self
+    }
+
+    pub fn display_offset(mut self, offset: u8) -> Self {
+        self.display_offset = offset;
+        self
+    }
+
+    pub fn com_pins(mut self, alternative: bool, left_right_remap: bool) -> Self {
+        self.com_alternative = alternative;
+        self.com_left_right_remap = left_right_remap;
+        self
+    }
+
+    pub fn multiplex(mut self, rows: u8) -> Self {
+        self.multiplex = rows;
+        self
+    }
+
+    pub fn precharge(mut self, phase1: u8, phase2: u8) -> Self {
+        self.precharge = (phase1, phase2);
+        self
+    }
+
+    pub fn vcomh(mut self, level: VcomhLevel) -> Self {
+        self.vcomh = level;
+        self
+    }
+
+    pub fn addressing_mode(mut self, mode: AddressingMode) -> Self {
+        self.addressing_mode = mode;
+        self
+    }
+
+    pub fn column_offset(mut self, offset: u8) -> Self {
+        self.column_offset = offset;
+        self
+    }
+
+    pub fn contrast(mut self, contrast: u8) -> Self {
+        self.contrast = contrast;
+        self
+    }
+
+    pub fn orientation(mut self, orientation: Orientation) -> Self {
+        self.orientation = orientation;
+        self
+    }
+
+    /// The initialisation sequence for `controller`, sent after the reset pulse.
+    /// Commands the controller lacks are replaced by Nop
+    pub fn init_commands(&self, controller: Controller) -> [Command; 19] {
+        let [segment_remap, com_scan] = self.orientation.commands();
+        let [extra_0, extra_1, extra_2] = controller.extra_init_commands();
+        let supply = controller.supply_command(self.vcc == VccSource::Internal).unwrap_or(Command::Nop);
+        let addressing = if controller.page_addressing_only() {
+            Command::Nop
+        } else {
+            Command::AddressingMode(self.addressing_mode)
+        };
+        [
+            Command::DisplayOn(false),
+            Command::ClockDiv { divide: self.clock_divide, oscillator: self.oscillator },
+            Command::Multiplex(self.multiplex),
+            Command::DisplayOffset(self.display_offset),
+            Command::StartLine(0),
+            supply,
+            addressing,
+            segment_remap,
+            com_scan,
+            Command::ComPins { alternative: self.com_alternative, left_right_remap: self.com_left_right_remap },
+            Command::Contrast(self.contrast),
+            Command::Precharge { phase1: self.precharge.0, phase2: self.precharge.1 },
+            Command::VcomhDeselect(self.vcomh),
+            extra_0,
+            extra_1,
+            extra_2,
+            Command::EntireDisplayOn(false),
+            Command::Invert(false),
+            Command::DisplayOn(true),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::vec::Vec;
+
+    // Bytes begin() sends, without the Nop padding
+    fn stream(config: &Config, controller: Controller) -> Vec<u8> {
+        config.init_commands(controller)
+            .iter()
+            .filter(|cmd| **cmd != Command::Nop)
+            .flat_map(|cmd| cmd.bytes().as_slice().to_vec())
+            .collect()
+    }
+
+    #[test]
+    fn init_128x64_matches_the_adafruit_sequence() {
+        let expected = [
+            0xae, 0xd5, 0x80, 0xa8, 0x3f, 0xd3, 0x00, 0x40, 0x8d, 0x14, 0x20, 0x00, 0xa1, 0xc8,
+            0xda, 0x12, 0x81, 0xcf, 0xd9, 0xf1, 0xdb, 0x40, 0xa4, 0xa6, 0xaf,
+        ];
+        assert_eq!(stream(&Config::for_panel(128, 64), Controller::Ssd1306), expected);
+    }
+
+    #[test]
+    fn init_128x32_matches_the_adafruit_sequence() {
+        let expected = [
+            0xae, 0xd5, 0x80, 0xa8, 0x1f, 0xd3, 0x00, 0x40, 0x8d, 0x14, 0x20, 0x00, 0xa1, 0xc8,
+            0xda, 0x02, 0x81, 0x8f, 0xd9, 0xf1, 0xdb, 0x40, 0xa4, 0xa6, 0xaf,
+        ];
+        assert_eq!(stream(&Config::for_panel(128, 32), Controller::Ssd1306), expected);
+    }
+
+    #[test]
+    fn init_external_vcc_matches_the_adafruit_sequence() {
+        let expected = [
+            0xae, 0xd5, 0x80, 0xa8, 0x3f, 0xd3, 0x00, 0x40, 0x8d, 0x10, 0x20, 0x00, 0xa1, 0xc8,
+            0xda, 0x12, 0x81, 0x9f, 0xd9, 0x22, 0xdb, 0x40, 0xa4, 0xa6, 0xaf,
+        ];
+        assert_eq!(stream(&Config::for_panel(128, 64).vcc(VccSource::External), Controller::Ssd1306), expected);
+    }
+}
@@ -0,0 +1,142 @@
+/*
+ *  Controller chips that share the SSD1306 command set.
+ *  The differences are limited to the RAM width, the addressing modes, how the
+ *  panel's high voltage is switched and a few extra init commands, so AdafruitGFX
+ *  draws the same for all of them.
+ */
+
+use crate::ssd1306_command::{ Command, PumpVoltage };
+
+/// Controller chip on the module
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Controller {
+    #[default]
+    Ssd1306,
+    Sh1106,     // 132 column RAM, page addressing only, DC-DC converter instead of the charge pump
+    Ssd1309,    // no internal charge pump, VCC is always supplied by the module
+    Ssd1305,    // 132 column RAM, external VCC, area colour and lookup table commands
+    Ssd1315(PumpVoltage), // SSD1306 compatible, selectable charge pump voltage
+}
+
+impl Controller {
+    /// Columns in the controller RAM
+    pub fn ram_columns(&self) -> u8 {
+        match self {
+            Controller::Sh1106 | Controller::Ssd1305 => 132,
+            _ => 128,
+        }
+    }
+
+    /// Column window command (0x21) for RAM columns start..=end, clamped to the RAM width
+    pub fn column_address(&self, start: u8, end: u8) -> Command {
+        let last = self.ram_columns() - 1;
+        Command::ColumnAddress { start: start.min(last), end: end.min(last) }
+    }
+
+    /// RAM column wired to the first segment of a 128 column panel
+    pub fn column_offset(&self) -> u8 {
+        match self {
+            Controller::Sh1106 => 2,
+            Controller::Ssd1305 => 4,
+            _ => 0,
+        }
+    }
+
+    /// true when the controller has no horizontal/vertical addressing (commands 0x20-0x22)
+    pub fn page_addressing_only(&self) -> bool {
+        *self == Controller::Sh1106
+    }
+
+    /// Command switching the internal high voltage supply, None when there is none
+    pub fn supply_command(&self, on: bool) -> Option<Command> {
+        match self {
+            Controller::Ssd1306 => Some(Command::ChargePump(on)),
+            Controller::Ssd1315(voltage) if on => Some(Command::ChargePumpVoltage(*voltage)),
+            Controller::Ssd1315(_) => Some(Command::ChargePump(false)),
+            Controller::Sh1106 => Some(Command::DcDc(on)),
+            Controller::Ssd1309 | Controller::Ssd1305 => None,
+        }
+    }
+
+    /// Controller specific commands sent by begin() before the display is switched on,
+    /// padded with Nop
+    pub fn extra_init_commands(&self) -> [Command; 3] {
+        match self {
+            Controller::Ssd1305 => [
+                Command::ExternalVcc,
+                Command::AreaColour { colour: false, low_power: true },
+                Command::LookupTable([63, 63, 63, 63]),
+            ],
+            _ => [Command::Nop; 3],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::adafruit_ssd1306_h::Display;
+    use crate::config::Config;
+    use crate::mock;
+    use std::vec::Vec;
+
+    const NOP: &[u8] = &[0xe3];
+
+    // Commands begin() sends to a 128x64 panel of `controller`
+    fn init(controller: Controller) -> Vec<Vec<u8>> {
+        let mut display = mock::display(128, 64, controller);
+        display.begin(&Config::for_panel(128, 64));
+        display.i2c.commands(0x3c)
+    }
+
+    // The 128x64 sequence with the controller specific commands in between
+    fn expected<'a>(supply: &'a [u8], addressing: &'a [u8], extra: [&'a [u8]; 3]) -> [&'a [u8]; 19] {
+        [
+            &[0xae], &[0xd5, 0x80], &[0xa8, 0x3f], &[0xd3, 0x00], &[0x40], supply, addressing,
+            &[0xa1], &[0xc8], &[0xda, 0x12], &[0x81, 0xcf], &[0xd9, 0xf1], &[0xdb, 0x40],
+            extra[0], extra[1], extra[2], &[0xa4], &[0xa6], &[0xaf],
+        ]
+    }
+
+    #[test]
+    fn ssd1306_init() {
+        assert_eq!(init(Controller::Ssd1306), expected(&[0x8d, 0x14], &[0x20, 0x00], [NOP; 3]));
+    }
+
+    #[test]
+    fn sh1106_init() {
+        assert_eq!(init(Controller::Sh1106), expected(&[0xad, 0x8b], NOP, [NOP; 3]));
+    }
+
+    #[test]
+    fn ssd1309_init() {
+        assert_eq!(init(Controller::Ssd1309), expected(NOP, &[0x20, 0x00], [NOP; 3]));
+    }
+
+    #[test]
+    fn ssd1305_init() {
+        let extra: [&[u8]; 3] = [&[0xad, 0x8e], &[0xd8, 0x05], &[0x91, 0x3f, 0x3f, 0x3f, 0x3f]];
+        assert_eq!(init(Controller::Ssd1305), expected(NOP, &[0x20, 0x00], extra));
+    }
+
+    #[test]
+    fn ssd1315_init() {
+        assert_eq!(init(Controller::Ssd1315(PumpVoltage::V9_0)), expected(&[0x8d, 0x95], &[0x20, 0x00], [NOP; 3]));
+    }
+
+    #[test]
+    fn ssd1305_show_uses_the_offset_window() {
+        let mut display = mock::display(128, 64, Controller::Ssd1305);
+        display.show();
+        assert_eq!(display.i2c.commands(0x3c), [[0x40].to_vec(), [0x21, 4, 131].to_vec(), [0x22, 0, 7].to_vec()]);
+        assert_eq!(display.i2c.data(0x3c).len(), 1024);
+    }
+
+    #[test]
+    fn column_address_is_clamped_to_the_ram() {
+        assert_eq!(Controller::Ssd1306.column_address(0, 131), Command::ColumnAddress { start: 0, end: 127 });
+        assert_eq!(Controller::Ssd1305.column_address(4, 135), Command::ColumnAddress { start: 4, end: 131 });
+    }
+}
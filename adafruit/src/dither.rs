@@ -0,0 +1,170 @@
+/*
+ *  Dithering of 8-bit grayscale images into the monochrome buffer.
+ *  Threshold and Bayer ordered dithering work pixel by pixel, Floyd-Steinberg and
+ *  Atkinson diffuse the error over fixed-size rows so nothing is allocated.
+ */
+
+use crate::adafruit_gfx_h::{ Drawable, BLACK, WHITE };
+
+/// 8x8 Bayer ordered dither thresholds, 0-63
+pub const BAYER_8X8: [[u8; 8]; 8] = [
+    [ 0, 32,  8, 40,  2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44,  4, 36, 14, 46,  6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [ 3, 35, 11, 43,  1, 33,  9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47,  7, 39, 13, 45,  5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+/// Widest image the error diffusion modes handle, wider images are cut off
+pub const DITHER_MAX_WIDTH: usize = 256;
+
+/// Row-major 8-bit grayscale image, 0 is black (off) and 255 white (lit)
+#[derive(Clone, Copy, Debug)]
+pub struct GrayImage<'a> {
+    pub data: &'a [u8],
+    pub width: i16,
+    pub height: i16,
+}
+
+impl<'a> GrayImage<'a> {
+    pub fn new(data: &'a [u8], width: i16, height: i16) -> Self {
+        assert!(data.len() >= (width.max(0) as usize) * (height.max(0) as usize), "data too short for width x height");
+        GrayImage { data, width, height }
+    }
+
+    fn value(&self, x: i16, y: i16) -> u8 {
+        self.data[(y as usize) * (self.width as usize) + x as usize]
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DitherMode {
+    Threshold(u8),  // lit when the value is at least the threshold
+    Bayer,          // ordered dithering with BAYER_8X8
+    FloydSteinberg,
+    Atkinson,       // diffuses 3/4 of the error, keeps more contrast
+}
+
+/// Draw `image` with its top left corner at (x, y)
+pub fn draw_gray<D: Drawable>(gfx: &mut D, x: i16, y: i16, image: &GrayImage, mode: DitherMode) {
+    match mode {
+        DitherMode::Threshold(level) => {
+            for j in 0..image.height {
+                for i in 0..image.width {
+                    let color = if image.value(i, j) >= level { WHITE } else { BLACK };
+                    gfx.draw_pixel(x + i, y + j, color);
+                }
+            }
+        }
+        DitherMode::Bayer => {
+            for j in 0..image.height {
+                for i in 0..image.width {
+                    // Thresholds 2..254 in steps of 4, so 0 stays black and 255 white
+                    let level = BAYER_8X8[(j % 8) as usize][(i % 8) as usize] as u16 * 4 + 2;
+                    let color = if image.value(i, j) as u16 >= level { WHITE } else { BLACK };
+                    gfx.draw_pixel(x + i, y + j, color);
+                }
+            }
+        }
+        DitherMode::FloydSteinberg | DitherMode::Atkinson => diffuse(gfx, x, y, image, mode),
+    }
+}
+
+// Error diffusion over three rows of accumulated error, padded by 2 on each side
+fn diffuse<D: Drawable>(gfx: &mut D, x: i16, y: i16, image: &GrayImage, mode: DitherMode) {
+    let width = core::cmp::min(image.width.max(0) as usize, DITHER_MAX_WIDTH);
+    let mut rows = [[0i16; DITHER_MAX_WIDTH + 4]; 3];
+
+    for j in 0..image.height {
+        let (cur, next, after) = ((j % 3) as usize, ((j + 1) % 3) as usize, ((j + 2) % 3) as usize);
+
+        for i in 0..width {
+            let c = i + 2;
+            let value = image.value(i as i16, j) as i16 + rows[cur][c];
+            let (color, error) = if value >= 128 { (WHITE, value - 255) } else { (BLACK, value) };
+            gfx.draw_pixel(x + i as i16, y + j, color);
+
+            if mode == DitherMode::FloydSteinberg {
+                rows[cur][c + 1] += error * 7 / 16;
+                rows[next][c - 1] += error * 3 / 16;
+                rows[next][c] += error * 5 / 16;
+                rows[next][c + 1] += error / 16;
+            } else {
+                let e = error / 8;
+                rows[cur][c + 1] += e;
+                rows[cur][c + 2] += e;
+                rows[next][c - 1] += e;
+                rows[next][c] += e;
+                rows[next][c + 1] += e;
+                rows[after][c] += e;
+            }
+        }
+        // Reused for row j + 3
+        rows[cur] = [0; DITHER_MAX_WIDTH + 4];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adafruit_gfx_h::AdafruitGFX;
+
+    fn lit(gfx: &AdafruitGFX) -> u32 {
+        gfx.buffer.iter().map(|b| b.count_ones()).sum()
+    }
+
+    #[test]
+    #[should_panic(expected = "data too short")]
+    fn new_rejects_short_data() {
+        GrayImage::new(&[0; 63], 8, 8);
+    }
+
+    #[test]
+    fn threshold_splits_at_the_level() {
+        let mut data = [0u8; 256];
+        for (i, value) in data.iter_mut().enumerate() {
+            *value = i as u8;
+        }
+        let mut gfx: AdafruitGFX = AdafruitGFX::new(128, 64);
+        draw_gray(&mut gfx, 0, 0, &GrayImage::new(&data, 16, 16), DitherMode::Threshold(100));
+        for i in 0..256 {
+            let expected = if i >= 100 { WHITE } else { BLACK };
+            assert_eq!(gfx.get_pixel(i % 16, i / 16), expected, "value {}", i);
+        }
+    }
+
+    #[test]
+    fn bayer_lights_half_of_a_flat_half_gray() {
+        let data = [128u8; 64 * 64];
+        let mut gfx: AdafruitGFX = AdafruitGFX::new(128, 64);
+        draw_gray(&mut gfx, 0, 0, &GrayImage::new(&data, 64, 64), DitherMode::Bayer);
+        assert_eq!(lit(&gfx), 64 * 64 / 2);
+        // Every 8x8 cell on its own is half lit too
+        let cell = (0..8).flat_map(|y| (0..8).map(move |x| (x, y))).filter(|&(x, y)| gfx.get_pixel(x + 8, y + 8) == WHITE).count();
+        assert_eq!(cell, 32);
+    }
+
+    fn assert_mean_kept(mode: DitherMode, value: u8) {
+        let data = [value; 128 * 64];
+        let mut gfx: AdafruitGFX = AdafruitGFX::new(128, 64);
+        draw_gray(&mut gfx, 0, 0, &GrayImage::new(&data, 128, 64), mode);
+        let expected = 128 * 64 * value as u32 / 255;
+        assert!(lit(&gfx).abs_diff(expected) <= 128 * 64 / 50, "{:?} at {}: {} lit, {} expected", mode, value, lit(&gfx), expected);
+    }
+
+    #[test]
+    fn floyd_steinberg_keeps_the_mean_level() {
+        for value in [64, 128, 192] {
+            assert_mean_kept(DitherMode::FloydSteinberg, value);
+        }
+    }
+
+    #[test]
+    fn atkinson_keeps_the_mean_level_of_half_gray() {
+        // The 1/4 of the error Atkinson drops pushes other levels towards black or white
+        assert_mean_kept(DitherMode::Atkinson, 128);
+    }
+}
@@ -0,0 +1,246 @@
+/*
+ *  Four gray levels by temporal dithering (frame-rate control).
+ *  GrayFrame stores 2 bits per pixel as two page-major bitplanes. GrayScheduler shows
+ *  the high plane for two slots and the low plane for one, so a pixel is lit
+ *  0, 1, 2 or 3 slots out of 3. Only the pages that differ from what is on the
+ *  panel are sent, which keeps the I2C traffic low enough for a steady cadence.
+ *  GraySimulator averages frames to check the perceived levels without a panel.
+ */
+
+use crate::adafruit_gfx_h::{ AdafruitGFX, Drawable, BLACK, WHITE };
+use crate::adafruit_ssd1306::MyGpioPin;
+use crate::adafruit_ssd1306_h::AdafruitSSD1306;
+use crate::clock::{ Clock, ClockDuration, ClockInstant };
+use crate::dither::GrayImage;
+use embedded_hal::delay::DelayNs;
+use embedded_hal::i2c::I2c;
+
+/// Highest gray level, fully lit
+pub const GRAY_MAX: u8 = 3;
+
+/// Plane shown in each slot of the cycle: 1 = high bit, 0 = low bit
+pub const GRAY_SEQUENCE: [usize; 3] = [1, 1, 0];
+
+/// 2-bit per pixel framebuffer, same size and rotation rules as the display's AdafruitGFX
+pub struct GrayFrame {
+    pub planes: [AdafruitGFX; 2],   // low and high bit of each pixel
+}
+
+impl GrayFrame {
+    pub fn new(w: i16, h: i16) -> Self {
+        GrayFrame { planes: [AdafruitGFX::new(w, h), AdafruitGFX::new(w, h)] }
+    }
+
+    pub fn width(&self) -> i16 {
+        self.planes[0].width
+    }
+
+    pub fn height(&self) -> i16 {
+        self.planes[0].height
+    }
+
+    pub fn set_rotation(&mut self, r: u8) {
+        self.planes[0].set_rotation(r);
+        self.planes[1].set_rotation(r);
+    }
+
+    pub fn clear(&mut self) {
+        self.planes[0].buffer.fill(0);
+        self.planes[1].buffer.fill(0);
+    }
+
+    /// Set pixel (x, y) to `level`, 0 (off) to GRAY_MAX
+    pub fn draw_pixel(&mut self, x: i16, y: i16, level: u8) {
+        let level = level.min(GRAY_MAX);
+        self.planes[0].draw_pixel(x, y, if level & 1 != 0 { WHITE } else { BLACK });
+        self.planes[1].draw_pixel(x, y, if level & 2 != 0 { WHITE } else { BLACK });
+    }
+
+    pub fn get_pixel(&self, x: i16, y: i16) -> u8 {
+        self.planes[0].get_pixel(x, y) | (self.planes[1].get_pixel(x, y) << 1)
+    }
+
+    pub fn fill_rect(&mut self, x: i16, y: i16, w: i16, h: i16, level: u8) {
+        for j in y..y + h {
+            for i in x..x + w {
+                self.draw_pixel(i, j, level);
+            }
+        }
+    }
+
+    /// Draw an 8-bit grayscale image, quantised to the 4 levels
+    pub fn draw_image(&mut self, x: i16, y: i16, image: &GrayImage) {
+        for j in 0..image.height {
+            for i in 0..image.width {
+                let value = image.data[(j as usize) * (image.width as usize) + i as usize];
+                self.draw_pixel(x + i, y + j, value >> 6);
+            }
+        }
+    }
+}
+
+/// Pushes the bitplanes of a GrayFrame to a display at a fixed cadence
+pub struct GrayScheduler {
+    pub interval: ClockDuration,    // time per slot, one cycle is 3 slots
+    slot: usize,
+    next_due: Option<ClockInstant>,
+}
+
+impl GrayScheduler {
+    /// Keep the interval short enough that a cycle (3 x interval) does not flicker,
+    /// e.g. 5-8 ms with a 400 kHz bus
+    pub fn new(interval: ClockDuration) -> Self {
+        GrayScheduler { interval, slot: 0, next_due: None }
+    }
+
+    /// Send the first plane completely, the display buffer then mirrors the panel
+    pub fn start<I2C, GPIO, DELAY, E>(&mut self, frame: &GrayFrame, display: &mut AdafruitSSD1306<I2C, GPIO, DELAY>)
+        where
+            DELAY: DelayNs,
+            GPIO: MyGpioPin,
+            I2C: I2c<Error = E>
+    {
+        self.slot = 0;
+        self.next_due = None;
+        let plane = &frame.planes[GRAY_SEQUENCE[0]];
+        let len = (display.gfx.raw_width as usize) * (display.gfx.raw_height as usize) / 8;
+        display.gfx.buffer[..len].copy_from_slice(&plane.buffer[..len]);
+        let pages = ((1u16 << (display.gfx.raw_height / 8)) - 1) as u8;
+        display.show_pages(pages);
+    }
+
+    /// Show the next plane when its slot is due. Only pages that differ from the
+    /// display buffer are sent. Returns true when a slot was shown
+    pub fn poll<I2C, GPIO, DELAY, E, C>(&mut self, frame: &GrayFrame, display: &mut AdafruitSSD1306<I2C, GPIO, DELAY>, clock: &mut C) -> bool
+        where
+            DELAY: DelayNs,
+            GPIO: MyGpioPin,
+            I2C: I2c<Error = E>,
+            C: Clock
+    {
+        let now = clock.now();
+        let due = *self.next_due.get_or_insert(now);
+        if now < due {
+            return false;
+        }
+        // Stay on the cadence, but do not try to catch up on missed slots
+        self.next_due = Some(if now - due > self.interval { now + self.interval } else { due + self.interval });
+        self.slot = (self.slot + 1) % GRAY_SEQUENCE.len();
+
+        let plane = &frame.planes[GRAY_SEQUENCE[self.slot]];
+        let width = display.gfx.raw_width as usize;
+        let mut pages = 0u8;
+        for page in 0..(display.gfx.raw_height / 8) as usize {
+            let range = page * width..(page + 1) * width;
+            if display.gfx.buffer[range.clone()] != plane.buffer[range.clone()] {
+                display.gfx.buffer[range.clone()].copy_from_slice(&plane.buffer[range]);
+                pages |= 1 << page;
+            }
+        }
+        if pages != 0 {
+            display.show_pages(pages);
+        }
+        true
+    }
+}
+
+/// Averages shown frames per pixel, the way the eye integrates them.
+/// P is the number of pixels (width x height). Counts up to u16::MAX frames, call reset() to start over
+pub struct GraySimulator<const P: usize = 8192> {
+    pub width: i16,
+    pub height: i16,
+    lit: [u16; P],  // frames in which each pixel was lit
+    frames: u16,
+}
+
+impl<const P: usize> GraySimulator<P> {
+    pub fn new(width: i16, height: i16) -> Self {
+        assert!((width as usize) * (height as usize) <= P, "simulator too small for width x height");
+        GraySimulator { width, height, lit: [0; P], frames: 0 }
+    }
+
+    pub fn reset(&mut self) {
+        self.lit.fill(0);
+        self.frames = 0;
+    }
+
+    /// Add one shown frame, e.g. the display's gfx after each GrayScheduler::poll()
+    pub fn accumulate<const N: usize>(&mut self, shown: &AdafruitGFX<N>) {
+        // Stop with all counters at once so lit / frames stays exact
+        if self.frames == u16::MAX {
+            return;
+        }
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if shown.get_pixel(x, y) == WHITE {
+                    let i = (y as usize) * (self.width as usize) + x as usize;
+                    self.lit[i] += 1;
+                }
+            }
+        }
+        self.frames += 1;
+    }
+
+    /// Perceived level of pixel (x, y), 0 to 255
+    pub fn brightness(&self, x: i16, y: i16) -> u8 {
+        if self.frames == 0 {
+            return 0;
+        }
+        let lit = self.lit[(y as usize) * (self.width as usize) + x as usize] as u32;
+        (lit * 255 / self.frames as u32) as u8
+    }
+
+    /// Perceived level of pixel (x, y) rounded to 0..=GRAY_MAX, to compare with GrayFrame::get_pixel()
+    pub fn level(&self, x: i16, y: i16) -> u8 {
+        ((self.brightness(x, y) as u32 * GRAY_MAX as u32 + 127) / 255) as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controller::Controller;
+    use crate::mock;
+
+    struct TestClock {
+        now: ClockInstant,
+    }
+
+    impl Clock for TestClock {
+        fn now(&mut self) -> ClockInstant {
+            self.now
+        }
+    }
+
+    #[test]
+    fn scheduler_shows_the_frame_levels() {
+        let mut frame = GrayFrame::new(128, 64);
+        for level in 0..=GRAY_MAX {
+            frame.fill_rect(level as i16 * 32, 0, 32, 32, level);
+        }
+        frame.fill_rect(0, 32, 128, 32, 2);
+        frame.draw_pixel(5, 40, 1);
+        frame.draw_pixel(100, 60, 3);
+
+        let mut display = mock::display(128, 64, Controller::Ssd1306);
+        let mut clock = TestClock { now: ClockInstant::from_ticks(0) };
+        let mut scheduler = GrayScheduler::new(ClockDuration::millis(6));
+        let mut simulator: GraySimulator = GraySimulator::new(128, 64);
+
+        scheduler.start(&frame, &mut display);
+        simulator.accumulate(&display.gfx);
+        // 10 whole cycles of GRAY_SEQUENCE
+        for _ in 1..10 * GRAY_SEQUENCE.len() {
+            assert!(scheduler.poll(&frame, &mut display, &mut clock));
+            assert!(!scheduler.poll(&frame, &mut display, &mut clock));
+            simulator.accumulate(&display.gfx);
+            clock.now += scheduler.interval;
+        }
+
+        for y in 0..64 {
+            for x in 0..128 {
+                assert_eq!(simulator.level(x, y), frame.get_pixel(x, y), "pixel ({}, {})", x, y);
+            }
+        }
+    }
+}
@@ -19,3 +19,21 @@ pub mod adafruit_ssd1306_h;
 pub mod adafruit_ssd1306;
 pub mod glcdfont;
 pub mod adafruit_gfx_config;
+pub mod splash;
+pub mod ssd1306_command;
+pub mod clock;
+pub mod scroll;
+pub mod marquee;
+pub mod config;
+pub mod controller;
+pub mod window;
+pub mod transfer;
+pub mod tiled;
+pub mod sprite;
+pub mod transition;
+pub mod dither;
+pub mod gray;
+#[cfg(feature = "async")]
+pub mod adafruit_ssd1306_async;
+#[cfg(test)]
+mod mock;
@@ -0,0 +1,220 @@
+/*
+ *  Marquee / ticker widget.
+ *  Renders a string with the builtin 5x7 font into an off-screen strip and
+ *  scrolls it one pixel per tick inside a rectangle of the display buffer.
+ */
+
+use crate::adafruit_gfx_h::{ AdafruitGFX, Drawable, Rect, BLACK, WHITE };
+use crate::glcdfont::FONT;
+
+/// Longest strip a marquee can hold, in pixel columns (6 per character)
+pub const MARQUEE_MAX_COLUMNS: usize = 512;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MarqueeMode {
+    /// Scroll continuously, the text comes back in from the right after a gap as wide as the area
+    Loop,
+    /// Stop for the given number of ticks at the start and at the end, then jump back to the start
+    PauseAtEnds(u16),
+}
+
+pub struct Marquee {
+    strip: [u8; MARQUEE_MAX_COLUMNS],   // one byte per column, bit 0 on top
+    len: usize,
+    area: Rect,
+    mode: MarqueeMode,
+    offset: usize,
+    pause: u16,
+    drawn: bool,
+    pub color: u8,
+    pub bg: u8,
+}
+
+impl Marquee {
+    /// Marquee showing `text` in `area`. Text beyond MARQUEE_MAX_COLUMNS is cut off
+    pub fn new(text: &str, area: Rect, mode: MarqueeMode) -> Self {
+        let mut marquee = Self {
+            strip: [0; MARQUEE_MAX_COLUMNS],
+            len: 0,
+            area,
+            mode,
+            offset: 0,
+            pause: 0,
+            drawn: false,
+            color: WHITE,
+            bg: BLACK,
+        };
+        marquee.set_text(text);
+        marquee
+    }
+
+    /// Replace the text and restart from the beginning
+    pub fn set_text(&mut self, text: &str) {
+        self.len = 0;
+        for c in text.bytes() {
+            if self.len + 6 > MARQUEE_MAX_COLUMNS {
+                break;
+            }
+            for i in 0..5 {
+                self.strip[self.len + i] = FONT[(c as usize) * 5 + i];
+            }
+            self.strip[self.len + 5] = 0; // space between characters
+            self.len += 6;
+        }
+        self.restart();
+    }
+
+    pub fn restart(&mut self) {
+        self.offset = 0;
+        self.pause = self.pause_ticks();
+        self.drawn = false;
+    }
+
+    pub fn area(&self) -> Rect {
+        self.area
+    }
+
+    /// Advance one pixel and redraw. Returns the area to flush with show_region(), or None if nothing changed
+    pub fn tick(&mut self, gfx: &mut AdafruitGFX) -> Option<Rect> {
+        let width = self.area.w.max(0) as usize;
+        if self.len <= width {
+            // Fits without scrolling, draw it once
+            if self.drawn {
+                return None;
+            }
+        } else if self.pause > 0 {
+            self.pause -= 1;
+            if self.drawn {
+                return None;
+            }
+        } else {
+            match self.mode {
+                MarqueeMode::Loop => self.offset = (self.offset + 1) % (self.len + width),
+                MarqueeMode::PauseAtEnds(ticks) => {
+                    if self.offset + width >= self.len {
+                        self.offset = 0; // jump back after the pause at the end
+                    } else {
+                        self.offset += 1;
+                    }
+                    if self.offset == 0 || self.offset + width >= self.len {
+                        self.pause = ticks;
+                    }
+                }
+            }
+        }
+
+        self.draw(gfx);
+        Some(self.area)
+    }
+
+    /// Draw the visible part of the strip into the area
+    pub fn draw(&mut self, gfx: &mut AdafruitGFX) {
+        let loop_len = match self.mode {
+            MarqueeMode::Loop => self.len + self.area.w.max(0) as usize,
+            MarqueeMode::PauseAtEnds(_) => usize::MAX,
+        };
+        for col in 0..self.area.w {
+            let index = (self.offset + col as usize) % loop_len;
+            let column = if index < self.len { self.strip[index] } else { 0 };
+            for row in 0..self.area.h {
+                let on = row < 8 && (column >> row) & 1 != 0;
+                gfx.draw_pixel(self.area.x + col, self.area.y + row, if on { self.color } else { self.bg });
+            }
+        }
+        self.drawn = true;
+    }
+
+    fn pause_ticks(&self) -> u16 {
+        match self.mode {
+            MarqueeMode::Loop => 0,
+            MarqueeMode::PauseAtEnds(ticks) => ticks,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adafruit_ssd1306_h::Display;
+    use crate::controller::Controller;
+    use crate::mock;
+
+    const TEXT: &str = "ABCDEFGH";     // 48 columns, wider than the area
+    const AREA: Rect = Rect { x: 10, y: 8, w: 20, h: 8 };
+
+    // Column `index` of TEXT rendered with the builtin font
+    fn text_column(index: usize) -> u8 {
+        let c = TEXT.as_bytes()[index / 6] as usize;
+        if index % 6 == 5 { 0 } else { FONT[c * 5 + index % 6] }
+    }
+
+    fn assert_shows(gfx: &AdafruitGFX, offset: usize) {
+        for col in 0..AREA.w {
+            let column = text_column(offset + col as usize);
+            for row in 0..8 {
+                let expected = if (column >> row) & 1 != 0 { WHITE } else { BLACK };
+                assert_eq!(gfx.get_pixel(AREA.x + col, AREA.y + row), expected, "offset {} column {} row {}", offset, col, row);
+            }
+        }
+    }
+
+    #[test]
+    fn advances_one_pixel_per_tick() {
+        let mut gfx = AdafruitGFX::new(128, 64);
+        let mut marquee = Marquee::new(TEXT, AREA, MarqueeMode::Loop);
+        for offset in 1..=28 {
+            assert_eq!(marquee.tick(&mut gfx), Some(AREA));
+            assert_shows(&gfx, offset);
+        }
+    }
+
+    #[test]
+    fn pauses_at_both_ends() {
+        let mut gfx = AdafruitGFX::new(128, 64);
+        let mut marquee = Marquee::new(TEXT, AREA, MarqueeMode::PauseAtEnds(3));
+        let end = TEXT.len() * 6 - AREA.w as usize;
+
+        // Drawn once at the start, then held for the rest of the pause
+        assert_eq!(marquee.tick(&mut gfx), Some(AREA));
+        assert_shows(&gfx, 0);
+        assert_eq!(marquee.tick(&mut gfx), None);
+        assert_eq!(marquee.tick(&mut gfx), None);
+
+        for offset in 1..=end {
+            assert_eq!(marquee.tick(&mut gfx), Some(AREA));
+            assert_shows(&gfx, offset);
+        }
+        for _ in 0..3 {
+            assert_eq!(marquee.tick(&mut gfx), None);
+        }
+
+        // Back to the start, paused again
+        assert_eq!(marquee.tick(&mut gfx), Some(AREA));
+        assert_shows(&gfx, 0);
+        for _ in 0..3 {
+            assert_eq!(marquee.tick(&mut gfx), None);
+        }
+        assert_eq!(marquee.tick(&mut gfx), Some(AREA));
+        assert_shows(&gfx, 1);
+    }
+
+    #[test]
+    fn tick_touches_and_flushes_only_the_area() {
+        let mut display = mock::display(128, 64, Controller::Ssd1306);
+        display.gfx.fill_screen(WHITE);
+        let mut marquee = Marquee::new(TEXT, AREA, MarqueeMode::Loop);
+        let region = marquee.tick(&mut display.gfx).unwrap();
+        display.show_region(region);
+
+        for y in 0..64 {
+            for x in 0..128 {
+                if !(AREA.x..AREA.x + AREA.w).contains(&x) || !(AREA.y..AREA.y + AREA.h).contains(&y) {
+                    assert_eq!(display.gfx.get_pixel(x, y), WHITE, "pixel ({}, {}) outside the area", x, y);
+                }
+            }
+        }
+        // Columns 10-29 of page 1 only
+        assert_eq!(display.i2c.commands(0x3c), [[0x21, 10, 29].to_vec(), [0x22, 1, 1].to_vec()]);
+        assert_eq!(display.i2c.data(0x3c), display.gfx.buffer[128 + 10..128 + 30]);
+    }
+}
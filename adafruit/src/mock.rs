@@ -0,0 +1,121 @@
+/*
+ *  Test doubles for the host tests: an I2C bus that records every write, a reset
+ *  pin and a delay that returns at once.
+ */
+
+extern crate std;
+
+use crate::adafruit_gfx_h::AdafruitGFX;
+use crate::adafruit_ssd1306_h::{ AdafruitSSD1306, Display, SSD1306_I2C_ADDRESS };
+use crate::controller::Controller;
+use core::convert::Infallible;
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{ self, OutputPin };
+use embedded_hal::i2c::{ ErrorKind, ErrorType, I2c, NoAcknowledgeSource, Operation };
+use std::vec::Vec;
+
+/// I2C bus recording the writes, only the addresses in `devices` acknowledge
+pub struct MockI2c {
+    pub devices: Vec<u8>,
+    pub writes: Vec<(u8, Vec<u8>)>,
+}
+
+impl MockI2c {
+    pub fn new(devices: &[u8]) -> Self {
+        MockI2c { devices: devices.to_vec(), writes: Vec::new() }
+    }
+
+    /// Command writes to `address`, without the 0x00 control byte
+    pub fn commands(&self, address: u8) -> Vec<Vec<u8>> {
+        self.writes.iter()
+            .filter(|(a, bytes)| *a == address && bytes[0] == 0x00)
+            .map(|(_, bytes)| bytes[1..].to_vec())
+            .collect()
+    }
+
+    /// Data written to `address`, all transfers joined, without the 0x40 control bytes
+    pub fn data(&self, address: u8) -> Vec<u8> {
+        self.writes.iter()
+            .filter(|(a, bytes)| *a == address && bytes[0] == 0x40)
+            .flat_map(|(_, bytes)| bytes[1..].to_vec())
+            .collect()
+    }
+}
+
+impl ErrorType for MockI2c {
+    type Error = ErrorKind;
+}
+
+impl I2c for MockI2c {
+    fn transaction(&mut self, address: u8, operations: &mut [Operation<'_>]) -> Result<(), Self::Error> {
+        if !self.devices.contains(&address) {
+            return Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address));
+        }
+        for operation in operations {
+            match operation {
+                Operation::Write(bytes) => self.writes.push((address, bytes.to_vec())),
+                Operation::Read(buffer) => buffer.fill(0),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct MockPin {
+    pub high: bool,
+}
+
+impl digital::ErrorType for MockPin {
+    type Error = Infallible;
+}
+
+impl OutputPin for MockPin {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.high = false;
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.high = true;
+        Ok(())
+    }
+}
+
+pub struct NoDelay;
+
+impl DelayNs for NoDelay {
+    fn delay_ns(&mut self, _ns: u32) {}
+}
+
+pub type MockDisplay = AdafruitSSD1306<MockI2c, MockPin, NoDelay>;
+
+/// A w x h display of `controller` at 0x3C on its own bus
+pub fn display(w: i16, h: i16, controller: Controller) -> MockDisplay {
+    let i2c = MockI2c::new(&[SSD1306_I2C_ADDRESS]);
+    AdafruitSSD1306::new(i2c, SSD1306_I2C_ADDRESS, MockPin::default(), NoDelay, AdafruitGFX::new(w, h), controller)
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::i2c::I2c for MockI2c {
+    async fn transaction(&mut self, address: u8, operations: &mut [Operation<'_>]) -> Result<(), Self::Error> {
+        I2c::transaction(self, address, operations)
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::delay::DelayNs for NoDelay {
+    async fn delay_ns(&mut self, _ns: u32) {}
+}
+
+/// Run a future to completion on the current thread, enough for the mocks which never wait
+#[cfg(feature = "async")]
+pub fn block_on<F: core::future::Future>(future: F) -> F::Output {
+    let mut future = core::pin::pin!(future);
+    let mut cx = core::task::Context::from_waker(std::task::Waker::noop());
+    loop {
+        if let core::task::Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
@@ -0,0 +1,118 @@
+/*
+ *  Hardware scroll configuration.
+ *  Describes one continuous scroll (commands 0x26/0x27 or 0x29/0x2A) over a page range,
+ *  optionally limited to a column range and to a vertical scroll area (command 0xA3).
+ */
+
+use crate::ssd1306_command::{ Command, ScrollDirection, ScrollInterval };
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScrollKind {
+    Horizontal,
+    /// Horizontal plus `vertical_offset` rows up per step
+    Diagonal { vertical_offset: u8 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScrollConfig {
+    pub kind: ScrollKind,
+    pub direction: ScrollDirection,
+    pub start_page: u8,
+    pub end_page: u8,
+    pub interval: ScrollInterval,
+    pub columns: Option<(u8, u8)>,          // start/end column of a horizontal scroll, None sends 0x00/0xFF
+    pub vertical_area: Option<(u8, u8)>,    // rows fixed at the top, rows in the scroll area
+}
+
+impl ScrollConfig {
+    /// Horizontal scroll of the whole screen, one column every 5 frames
+    pub fn horizontal(direction: ScrollDirection) -> Self {
+        Self {
+            kind: ScrollKind::Horizontal,
+            direction,
+            start_page: 0,
+            end_page: 7,
+            interval: ScrollInterval::Frames5,
+            columns: None,
+            vertical_area: None,
+        }
+    }
+
+    /// Horizontal scroll combined with a vertical move of `vertical_offset` rows per step
+    pub fn diagonal(direction: ScrollDirection, vertical_offset: u8) -> Self {
+        Self {
+            kind: ScrollKind::Diagonal { vertical_offset },
+            ..Self::horizontal(direction)
+        }
+    }
+
+    /// Only scroll pages `start` to `end` (0-7), e.g. a single ticker line
+    pub fn pages(mut self, start: u8, end: u8) -> Self {
+        self.start_page = start;
+        self.end_page = end;
+        self
+    }
+
+    pub fn interval(mut self, interval: ScrollInterval) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Only scroll columns `start` to `end` of a horizontal scroll. They are sent in the last
+    /// two bytes of 0x26/0x27, where early SSD1306 revisions expect the dummies 0x00 and 0xFF,
+    /// so only set them on controllers with column support (SSD1309, SSD1315).
+    /// Diagonal scrolls (0x29/0x2A) have no column range and panic here
+    pub fn columns(mut self, start: u8, end: u8) -> Self {
+        assert!(self.kind == ScrollKind::Horizontal, "diagonal scrolls have no column range");
+        self.columns = Some((start, end));
+        self
+    }
+
+    /// Keep `fixed_rows` at the top still and move the next `scroll_rows` rows vertically
+    pub fn vertical_area(mut self, fixed_rows: u8, scroll_rows: u8) -> Self {
+        self.vertical_area = Some((fixed_rows, scroll_rows));
+        self
+    }
+
+    /// The scroll setup command, to be sent while scrolling is deactivated
+    pub fn setup_command(&self) -> Command {
+        match self.kind {
+            ScrollKind::Horizontal => Command::HorizontalScroll {
+                direction: self.direction,
+                start_page: self.start_page,
+                interval: self.interval,
+                end_page: self.end_page,
+                columns: self.columns,
+            },
+            ScrollKind::Diagonal { vertical_offset } => Command::DiagonalScroll {
+                direction: self.direction,
+                start_page: self.start_page,
+                interval: self.interval,
+                end_page: self.end_page,
+                vertical_offset,
+            },
+        }
+    }
+
+    /// The vertical scroll area command, if an area is set
+    pub fn area_command(&self) -> Option<Command> {
+        self.vertical_area.map(|(fixed_rows, scroll_rows)| Command::VerticalScrollArea { fixed_rows, scroll_rows })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn horizontal_columns_are_sent() {
+        let config = ScrollConfig::horizontal(ScrollDirection::Left).pages(6, 7).columns(8, 119);
+        assert_eq!(config.setup_command().bytes().as_slice(), &[0x27, 0x00, 0x06, 0x00, 0x07, 8, 119]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn diagonal_rejects_columns() {
+        let _ = ScrollConfig::diagonal(ScrollDirection::Right, 1).columns(0, 63);
+    }
+}
@@ -0,0 +1,302 @@
+/*********************************************************************
+Splash screen support for the SSD1306 driver.
+
+The builtin Adafruit logo below comes from the original Adafruit SSD1306
+library and must be included in any redistribution that uses it.
+Disable the `adafruit-splash` cargo feature to leave it out of the binary.
+*********************************************************************/
+
+use crate::adafruit_gfx_h::{ bv, AdafruitGFX, Drawable };
+
+/// Adafruit logo, 128x64 in the SSD1306 page layout
+#[cfg(feature = "adafruit-splash")]
+pub const ADAFRUIT_LOGO: [u8; 1024] = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0x80, 0x80, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0x80, 0xc0,
+    0xc0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0xc0, 0xe0, 0xf0, 0xf8, 0xfc,
+    0xf8, 0xe0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0x80, 0x80, 0x80, 0x80, 0x00, 0x80, 0x80, 0x00,
+    0x00, 0x00, 0x00, 0x80, 0x80, 0x80, 0x80, 0x80, 0x00, 0xff, 0xff, 0xff, 0x00, 0x00,
+    0x00, 0x00, 0x80, 0x80, 0x80, 0x80, 0x00, 0x00, 0x80, 0x80, 0x00, 0x00, 0x80, 0xff,
+    0xff, 0x80, 0x80, 0x00, 0x80, 0x80, 0x00, 0x80, 0x80, 0x80, 0x80, 0x00, 0x80, 0x80,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0x80, 0x00, 0x00, 0x8c, 0x8e, 0x84, 0x00, 0x00,
+    0x80, 0xf8, 0xf8, 0xf8, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0xf0, 0xf0, 0xf0, 0xf0, 0xf0, 0xf0, 0xf0, 0xf0, 0xf0, 0xf0,
+    0xf0, 0xf0, 0xe0, 0xe0, 0xc0, 0x80, 0x00, 0xe0, 0xfc, 0xfe, 0xff, 0xff, 0xff, 0x7f,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0xfe, 0xff, 0xc7, 0x01, 0x01, 0x01, 0x01, 0x83, 0xff,
+    0xff, 0x00, 0x00, 0x7c, 0xfe, 0xc7, 0x01, 0x01, 0x01, 0x01, 0x83, 0xff, 0xff, 0xff,
+    0x00, 0x38, 0xfe, 0xc7, 0x83, 0x01, 0x01, 0x01, 0x83, 0xc7, 0xff, 0xff, 0x00, 0x00,
+    0x01, 0xff, 0xff, 0x01, 0x01, 0x00, 0xff, 0xff, 0x07, 0x01, 0x01, 0x01, 0x00, 0x00,
+    0x7f, 0xff, 0x80, 0x00, 0x00, 0x00, 0xff, 0xff, 0x7f, 0x00, 0x00, 0xff, 0xff, 0xff,
+    0x00, 0x00, 0x01, 0xff, 0xff, 0xff, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x0f, 0x3f, 0x7f, 0x7f, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xe7, 0xc7, 0xc7, 0x8f, 0x8f, 0x9f, 0xbf, 0xff, 0xff, 0xc3,
+    0xc0, 0xf0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc, 0xfc,
+    0xfc, 0xf8, 0xf8, 0xf0, 0xf0, 0xe0, 0xc0, 0x00, 0x01, 0x03, 0x03, 0x03, 0x03, 0x03,
+    0x01, 0x03, 0x03, 0x00, 0x00, 0x00, 0x00, 0x01, 0x03, 0x03, 0x03, 0x03, 0x01, 0x01,
+    0x03, 0x01, 0x00, 0x00, 0x00, 0x01, 0x03, 0x03, 0x03, 0x03, 0x01, 0x01, 0x03, 0x03,
+    0x00, 0x00, 0x00, 0x03, 0x03, 0x00, 0x00, 0x00, 0x03, 0x03, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x01, 0x03, 0x03, 0x03, 0x03, 0x03, 0x01, 0x00, 0x00, 0x00, 0x01,
+    0x03, 0x01, 0x00, 0x00, 0x00, 0x03, 0x03, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x80, 0xc0, 0xe0, 0xf0, 0xf9, 0xff, 0xff, 0xff, 0xff, 0xff, 0x3f,
+    0x1f, 0x0f, 0x87, 0xc7, 0xf7, 0xff, 0xff, 0x1f, 0x1f, 0x3d, 0xfc, 0xf8, 0xf8, 0xf8,
+    0xf8, 0x7c, 0x7d, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f, 0x3f, 0x0f,
+    0x07, 0x00, 0x30, 0x30, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xfe, 0xfe,
+    0xfc, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xe0, 0xc0, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x30, 0x30, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0xc0, 0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x7f, 0x7f, 0x3f, 0x1f, 0x0f, 0x07, 0x1f, 0x7f, 0xff, 0xff, 0xf8, 0xf8, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xfe, 0xf8, 0xe0, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0xfe, 0xfe, 0x00, 0x00, 0x00, 0xfc, 0xfe, 0xfc, 0x0c, 0x06,
+    0x06, 0x0e, 0xfc, 0xf8, 0x00, 0x00, 0xf0, 0xf8, 0x1c, 0x0e, 0x06, 0x06, 0x06, 0x0c,
+    0xff, 0xff, 0xff, 0x00, 0x00, 0xfe, 0xfe, 0x00, 0x00, 0x00, 0x00, 0xfc, 0xfe, 0xfc,
+    0x00, 0x18, 0x3c, 0x7e, 0x66, 0xe6, 0xce, 0x84, 0x00, 0x00, 0x06, 0xff, 0xff, 0x06,
+    0x06, 0xfc, 0xfe, 0xfc, 0x0c, 0x06, 0x06, 0x06, 0x00, 0x00, 0xfe, 0xfe, 0x00, 0x00,
+    0xc0, 0xf8, 0xfc, 0x4e, 0x46, 0x46, 0x46, 0x4e, 0x7c, 0x78, 0x40, 0x18, 0x3c, 0x76,
+    0xe6, 0xce, 0xcc, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x07, 0x0f, 0x1f,
+    0x1f, 0x3f, 0x3f, 0x3f, 0x3f, 0x1f, 0x0f, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0f, 0x0f, 0x00, 0x00, 0x00, 0x0f, 0x0f, 0x0f,
+    0x00, 0x00, 0x00, 0x00, 0x0f, 0x0f, 0x00, 0x00, 0x03, 0x07, 0x0e, 0x0c, 0x18, 0x18,
+    0x0c, 0x06, 0x0f, 0x0f, 0x0f, 0x00, 0x00, 0x01, 0x0f, 0x0e, 0x0c, 0x18, 0x0c, 0x0f,
+    0x07, 0x01, 0x00, 0x04, 0x0e, 0x0c, 0x18, 0x0c, 0x0f, 0x07, 0x00, 0x00, 0x00, 0x0f,
+    0x0f, 0x00, 0x00, 0x0f, 0x0f, 0x0f, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0f, 0x0f,
+    0x00, 0x00, 0x00, 0x07, 0x07, 0x0c, 0x0c, 0x18, 0x1c, 0x0c, 0x06, 0x06, 0x00, 0x04,
+    0x0e, 0x0c, 0x18, 0x0c, 0x0f, 0x07, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+/// How a 64-row image is fitted onto a 32-row panel when no 32-row image is given
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SplashFit {
+    /// Squeeze the image by merging every two rows into one
+    Scale,
+    /// Show the middle 32 rows of the image
+    Crop,
+}
+
+/// A monochrome image in the SSD1306 page layout (one byte = 8 vertical pixels)
+#[derive(Clone, Copy)]
+pub struct SplashImage<'a> {
+    pub data: &'a [u8],
+    pub width: i16,
+    pub height: i16,
+}
+
+impl<'a> SplashImage<'a> {
+    pub fn new(data: &'a [u8], width: i16, height: i16) -> Self {
+        Self { data, width, height }
+    }
+
+    fn pixel(&self, x: i16, y: i16) -> bool {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return false;
+        }
+        let index = (x + (y / 8) * self.width) as usize;
+        index < self.data.len() && (self.data[index] & bv((y % 8) as u8)) != 0
+    }
+}
+
+/// Splash screen description: an image per panel height, optional text and timing for the fade out
+pub struct Splash<'a> {
+    pub image_64: Option<SplashImage<'a>>,
+    pub image_32: Option<SplashImage<'a>>,
+    pub fit: SplashFit,
+    pub text: Option<(&'a str, i16, i16)>,
+    pub hold_ms: u32,       // time the splash stays at full contrast
+    pub fade_ms: u32,       // 0 disables the contrast fade
+}
+
+impl<'a> Splash<'a> {
+    /// Splash showing `image` (usually 128x64), fitted to smaller panels with `SplashFit::Scale`
+    pub fn new(image: SplashImage<'a>) -> Self {
+        Self {
+            image_64: Some(image),
+            image_32: None,
+            fit: SplashFit::Scale,
+            text: None,
+            hold_ms: 0,
+            fade_ms: 0,
+        }
+    }
+
+    /// The builtin Adafruit logo
+    #[cfg(feature = "adafruit-splash")]
+    pub fn adafruit() -> Splash<'static> {
+        Splash::new(SplashImage::new(&ADAFRUIT_LOGO, 128, 64))
+    }
+
+    /// Use a dedicated image on 32-row panels instead of fitting the 64-row one
+    pub fn with_image_32(mut self, image: SplashImage<'a>) -> Self {
+        self.image_32 = Some(image);
+        self
+    }
+
+    pub fn with_fit(mut self, fit: SplashFit) -> Self {
+        self.fit = fit;
+        self
+    }
+
+    /// Draw `text` on top of the image, starting at (x, y), using the current text colors
+    pub fn with_text(mut self, text: &'a str, x: i16, y: i16) -> Self {
+        self.text = Some((text, x, y));
+        self
+    }
+
+    /// Keep the splash for `hold_ms`, then fade the contrast down over `fade_ms`
+    pub fn with_fade(mut self, hold_ms: u32, fade_ms: u32) -> Self {
+        self.hold_ms = hold_ms;
+        self.fade_ms = fade_ms;
+        self
+    }
+
+    /// Compose the splash into the display buffer. Requires a show() call afterwards
+    pub fn draw(&self, gfx: &mut AdafruitGFX) {
+        let raw_w = gfx.raw_width;
+        let raw_h = gfx.raw_height;
+
+        match (self.image_32, self.image_64) {
+            (Some(image), _) if raw_h <= 32 => copy_image(gfx, &image, 0, 1),
+            (_, Some(image)) if raw_h < image.height => match self.fit {
+                SplashFit::Scale => {
+                    let scale = (image.height + raw_h - 1) / raw_h;
+                    copy_image(gfx, &image, 0, scale)
+                }
+                SplashFit::Crop => copy_image(gfx, &image, (image.height - raw_h) / 2, 1),
+            },
+            (_, Some(image)) => copy_image(gfx, &image, 0, 1),
+            (Some(image), None) => copy_image(gfx, &image, 0, 1),
+            (None, None) => {}
+        }
+
+        if let Some((text, x, y)) = self.text {
+            if x < raw_w && y < raw_h {
+                gfx.set_text_cursor(x, y);
+                gfx.write_string(text);
+            }
+        }
+    }
+}
+
+// Copy `image` into the raw (unrotated) buffer, starting at image row `first_row`.
+// With `scale` > 1 every `scale` image rows are merged (OR) into one panel row.
+fn copy_image(gfx: &mut AdafruitGFX, image: &SplashImage, first_row: i16, scale: i16) {
+    let width = core::cmp::min(gfx.raw_width, image.width);
+    for y in 0..gfx.raw_height {
+        for x in 0..width {
+            let src_y = first_row + y * scale;
+            let on = (0..scale).any(|k| image.pixel(x, src_y + k));
+            let index = (x + (y / 8) * gfx.raw_width) as usize;
+            if on {
+                gfx.buffer[index] |= bv((y % 8) as u8);
+            } else {
+                gfx.buffer[index] &= !bv((y % 8) as u8);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use super::*;
+    use crate::adafruit_gfx_h::{ BLACK, WHITE };
+    use std::vec::Vec;
+
+    // 128x64 image with only image row `row` lit
+    fn single_row(row: i16) -> [u8; 1024] {
+        let mut data = [0; 1024];
+        let page = (row / 8) as usize;
+        data[page * 128..(page + 1) * 128].fill(bv((row % 8) as u8));
+        data
+    }
+
+    fn lit_rows(gfx: &AdafruitGFX) -> Vec<i16> {
+        (0..gfx.height).filter(|&y| (0..gfx.width).all(|x| gfx.get_pixel(x, y) == WHITE)).collect()
+    }
+
+    #[test]
+    fn scale_merges_row_pairs_on_32_row_panels() {
+        let data = single_row(41);
+        let mut gfx = AdafruitGFX::new(128, 32);
+        Splash::new(SplashImage::new(&data, 128, 64)).draw(&mut gfx);
+        assert_eq!(lit_rows(&gfx), [20]);
+    }
+
+    #[test]
+    fn crop_shows_the_middle_32_rows() {
+        let data = single_row(41);
+        let mut gfx = AdafruitGFX::new(128, 32);
+        Splash::new(SplashImage::new(&data, 128, 64)).with_fit(SplashFit::Crop).draw(&mut gfx);
+        assert_eq!(lit_rows(&gfx), [41 - 16]);
+    }
+
+    #[test]
+    fn image_32_is_used_as_is() {
+        let data_64 = single_row(41);
+        let data_32 = single_row(3);
+        let mut gfx = AdafruitGFX::new(128, 32);
+        Splash::new(SplashImage::new(&data_64, 128, 64))
+            .with_image_32(SplashImage::new(&data_32[..512], 128, 32))
+            .draw(&mut gfx);
+        assert_eq!(lit_rows(&gfx), [3]);
+    }
+
+    #[cfg(feature = "adafruit-splash")]
+    #[test]
+    fn adafruit_logo_on_32_rows() {
+        let logo = SplashImage::new(&ADAFRUIT_LOGO, 128, 64);
+        let mut gfx = AdafruitGFX::new(128, 32);
+        Splash::adafruit().draw(&mut gfx);
+        for y in 0..32 {
+            for x in 0..128 {
+                let expected = if logo.pixel(x, 2 * y) || logo.pixel(x, 2 * y + 1) { WHITE } else { BLACK };
+                assert_eq!(gfx.get_pixel(x, y), expected, "pixel ({}, {})", x, y);
+            }
+        }
+        // Nothing is written past the 512 bytes of a 128x32 panel
+        assert!(gfx.buffer[512..].iter().all(|&b| b == 0));
+    }
+
+    #[cfg(feature = "adafruit-splash")]
+    #[test]
+    fn text_is_drawn_over_the_logo() {
+        let mut logo_only = AdafruitGFX::new(128, 32);
+        Splash::adafruit().draw(&mut logo_only);
+
+        let mut gfx = AdafruitGFX::new(128, 32);
+        Splash::adafruit().with_text("Hi", 100, 24).draw(&mut gfx);
+
+        // Inside the two 6x8 character cells the text replaces the logo, elsewhere the logo is kept
+        let mut text: AdafruitGFX = AdafruitGFX::new(128, 32);
+        text.set_text_cursor(100, 24);
+        text.write_string("Hi");
+        for y in 0..32 {
+            for x in 0..128 {
+                let expected = if (100..112).contains(&x) && y >= 24 { text.get_pixel(x, y) } else { logo_only.get_pixel(x, y) };
+                assert_eq!(gfx.get_pixel(x, y), expected, "pixel ({}, {})", x, y);
+            }
+        }
+    }
+}
@@ -0,0 +1,316 @@
+/*
+ *  Sprites and frame animation.
+ *  A Sprite is a sequence of row-major bitmaps (MSB first, as draw_bitmap_rowmajor)
+ *  with optional masks and per-frame durations. An Animation plays it at a position
+ *  against a Clock and reports the rectangle to flush with show_region().
+ */
+
+use crate::adafruit_gfx_h::{ Drawable, Rect, WHITE };
+use crate::clock::{ Clock, ClockDuration, ClockInstant };
+
+/// One image of a sprite
+#[derive(Clone, Copy, Debug)]
+pub struct SpriteFrame<'a> {
+    pub bitmap: &'a [u8],
+    /// Pixels drawn from the bitmap, set and clear; without a mask only set bits are drawn
+    pub mask: Option<&'a [u8]>,
+    pub duration_ms: u32,   // 0 holds the frame
+}
+
+impl<'a> SpriteFrame<'a> {
+    pub fn new(bitmap: &'a [u8], duration_ms: u32) -> Self {
+        SpriteFrame { bitmap, mask: None, duration_ms }
+    }
+
+    pub fn with_mask(mut self, mask: &'a [u8]) -> Self {
+        self.mask = Some(mask);
+        self
+    }
+}
+
+/// Frames of the same size
+#[derive(Clone, Copy, Debug)]
+pub struct Sprite<'a> {
+    pub frames: &'a [SpriteFrame<'a>],
+    pub width: i16,
+    pub height: i16,
+}
+
+impl<'a> Sprite<'a> {
+    pub fn new(frames: &'a [SpriteFrame<'a>], width: i16, height: i16) -> Self {
+        Sprite { frames, width, height }
+    }
+
+    /// Draw frame `index` with its top left corner at (x, y)
+    pub fn draw<D: Drawable>(&self, gfx: &mut D, index: usize, x: i16, y: i16, color: u8) {
+        let frame = match self.frames.get(index) {
+            Some(frame) => frame,
+            None => return,
+        };
+        let byte_width = ((self.width + 7) / 8) as usize;
+        let bit = |data: &[u8], i: i16, j: i16| {
+            data[(j as usize) * byte_width + (i / 8) as usize] & (0x80 >> (i % 8)) != 0
+        };
+        for j in 0..self.height {
+            for i in 0..self.width {
+                let set = bit(frame.bitmap, i, j);
+                match frame.mask {
+                    Some(mask) if bit(mask, i, j) => gfx.draw_pixel(x + i, y + j, if set { color } else { color ^ 1 }),
+                    None if set => gfx.draw_pixel(x + i, y + j, color),
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PlayMode {
+    Loop,
+    Once,   // stop on the last frame
+}
+
+pub struct Animation<'a> {
+    pub sprite: Sprite<'a>,
+    pub mode: PlayMode,
+    pub color: u8,
+    /// Colour used by tick() to erase the previous position, None leaves the background alone
+    pub bg: Option<u8>,
+    frame: usize,
+    x: i16,
+    y: i16,
+    frame_start: Option<ClockInstant>,
+    drawn: Option<Rect>,    // where the sprite was last drawn
+    changed: bool,
+}
+
+impl<'a> Animation<'a> {
+    pub fn new(sprite: Sprite<'a>, x: i16, y: i16, mode: PlayMode) -> Self {
+        Animation {
+            sprite,
+            mode,
+            color: WHITE,
+            bg: None,
+            frame: 0,
+            x,
+            y,
+            frame_start: None,
+            drawn: None,
+            changed: true,
+        }
+    }
+
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    pub fn position(&self) -> (i16, i16) {
+        (self.x, self.y)
+    }
+
+    pub fn area(&self) -> Rect {
+        Rect::new(self.x, self.y, self.sprite.width, self.sprite.height)
+    }
+
+    pub fn move_to(&mut self, x: i16, y: i16) {
+        if (x, y) != (self.x, self.y) {
+            self.x = x;
+            self.y = y;
+            self.changed = true;
+        }
+    }
+
+    /// Back to the first frame, its time starts at the next update()
+    pub fn restart(&mut self) {
+        self.frame = 0;
+        self.frame_start = None;
+        self.changed = true;
+    }
+
+    /// true once a PlayMode::Once animation reached its last frame
+    pub fn finished(&self) -> bool {
+        self.mode == PlayMode::Once && self.frame + 1 >= self.sprite.frames.len()
+    }
+
+    /// Advance the frames by the time elapsed. Returns the area to redraw and flush,
+    /// the old and new position together, or None when nothing changed
+    pub fn update<C: Clock>(&mut self, clock: &mut C) -> Option<Rect> {
+        let now = clock.now();
+        let start = *self.frame_start.get_or_insert(now);
+        let mut elapsed = now.checked_duration_since(start).unwrap_or(ClockDuration::from_ticks(0));
+        let count = self.sprite.frames.len();
+
+        // Catch up on every frame that ended since the last update
+        while count > 0 && !self.finished() {
+            let duration = ClockDuration::millis(self.sprite.frames[self.frame].duration_ms as u64);
+            if duration.ticks() == 0 || elapsed < duration {
+                break;
+            }
+            elapsed -= duration;
+            self.frame = (self.frame + 1) % count;
+            self.frame_start = Some(now - elapsed);
+            self.changed = true;
+        }
+
+        if !self.changed {
+            return None;
+        }
+        Some(match self.drawn {
+            Some(previous) => previous.union(self.area()),
+            None => self.area(),
+        })
+    }
+
+    /// Draw the current frame, with transparency
+    pub fn draw<D: Drawable>(&mut self, gfx: &mut D) {
+        self.sprite.draw(gfx, self.frame, self.x, self.y, self.color);
+        self.drawn = Some(self.area());
+        self.changed = false;
+    }
+
+    /// update(), then erase the previous position with `bg` and draw the new frame.
+    /// Returns the area to pass to show_region()
+    pub fn tick<C: Clock, D: Drawable>(&mut self, gfx: &mut D, clock: &mut C) -> Option<Rect> {
+        let dirty = self.update(clock)?;
+        if let (Some(bg), Some(previous)) = (self.bg, self.drawn) {
+            gfx.fill_rect(previous.x, previous.y, previous.w, previous.h, bg);
+        }
+        self.draw(gfx);
+        Some(dirty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adafruit_gfx_h::{ AdafruitGFX, BLACK };
+
+    struct TestClock {
+        now: ClockInstant,
+    }
+
+    impl Clock for TestClock {
+        fn now(&mut self) -> ClockInstant {
+            self.now
+        }
+    }
+
+    // 8x2 frames, 100, 200 and 300 ms
+    const FRAME_0: [u8; 2] = [0x80, 0x00];
+    const FRAME_1: [u8; 2] = [0x40, 0x00];
+    const FRAME_2: [u8; 2] = [0x20, 0x00];
+    const FRAMES: [SpriteFrame; 3] = [
+        SpriteFrame { bitmap: &FRAME_0, mask: None, duration_ms: 100 },
+        SpriteFrame { bitmap: &FRAME_1, mask: None, duration_ms: 200 },
+        SpriteFrame { bitmap: &FRAME_2, mask: None, duration_ms: 300 },
+    ];
+
+    fn at(clock: &mut TestClock, ms: u64) -> &mut TestClock {
+        clock.now = ClockInstant::from_ticks(0) + ClockDuration::millis(ms);
+        clock
+    }
+
+    #[test]
+    fn frames_advance_with_the_clock() {
+        let mut gfx: AdafruitGFX = AdafruitGFX::new(128, 64);
+        let mut clock = TestClock { now: ClockInstant::from_ticks(0) };
+        let mut animation = Animation::new(Sprite::new(&FRAMES, 8, 2), 10, 10, PlayMode::Loop);
+
+        // The first update draws, its time starts the first frame
+        assert_eq!(animation.update(&mut clock), Some(Rect::new(10, 10, 8, 2)));
+        animation.draw(&mut gfx);
+        assert_eq!(animation.update(at(&mut clock, 99)), None);
+        assert_eq!(animation.frame(), 0);
+
+        assert_eq!(animation.tick(&mut gfx, at(&mut clock, 100)), Some(Rect::new(10, 10, 8, 2)));
+        assert_eq!(animation.frame(), 1);
+        assert_eq!(gfx.get_pixel(11, 10), WHITE);
+        assert_eq!(animation.update(at(&mut clock, 299)), None);
+        assert!(animation.update(at(&mut clock, 300)).is_some());
+        assert_eq!(animation.frame(), 2);
+    }
+
+    #[test]
+    fn catches_up_over_several_frames() {
+        let mut gfx: AdafruitGFX = AdafruitGFX::new(128, 64);
+        let mut clock = TestClock { now: ClockInstant::from_ticks(0) };
+        let mut animation = Animation::new(Sprite::new(&FRAMES, 8, 2), 0, 0, PlayMode::Loop);
+        animation.tick(&mut gfx, &mut clock);
+
+        // 350 ms ends frames 0 and 1, frame 2 started at 300
+        assert!(animation.tick(&mut gfx, at(&mut clock, 350)).is_some());
+        assert_eq!(animation.frame(), 2);
+        assert_eq!(animation.update(at(&mut clock, 599)), None);
+        assert!(animation.update(at(&mut clock, 600)).is_some());
+        assert_eq!(animation.frame(), 0);
+    }
+
+    #[test]
+    fn loop_wraps_and_once_stops_on_the_last_frame() {
+        let mut gfx: AdafruitGFX = AdafruitGFX::new(128, 64);
+        let mut clock = TestClock { now: ClockInstant::from_ticks(0) };
+        let mut looping = Animation::new(Sprite::new(&FRAMES, 8, 2), 0, 0, PlayMode::Loop);
+        let mut once = Animation::new(Sprite::new(&FRAMES, 8, 2), 0, 4, PlayMode::Once);
+        looping.tick(&mut gfx, &mut clock);
+        once.tick(&mut gfx, &mut clock);
+
+        looping.tick(&mut gfx, at(&mut clock, 700));
+        once.tick(&mut gfx, &mut clock);
+        assert_eq!(looping.frame(), 1);
+        assert!(!looping.finished());
+        assert_eq!(once.frame(), 2);
+        assert!(once.finished());
+
+        // Once holds its last frame for good
+        assert_eq!(once.tick(&mut gfx, at(&mut clock, 10_000)), None);
+        assert_eq!(once.frame(), 2);
+        once.restart();
+        assert!(once.update(&mut clock).is_some());
+        assert_eq!(once.frame(), 0);
+        assert!(!once.finished());
+    }
+
+    #[test]
+    fn mask_selects_the_pixels_drawn() {
+        let bitmap = [0b1010_1010];
+        let mask = [0b1111_0000];
+        let masked = [SpriteFrame::new(&bitmap, 0).with_mask(&mask)];
+        let plain = [SpriteFrame::new(&bitmap, 0)];
+
+        for background in [BLACK, WHITE] {
+            let mut gfx: AdafruitGFX = AdafruitGFX::new(128, 64);
+            gfx.fill_screen(background);
+            Sprite::new(&masked, 8, 1).draw(&mut gfx, 0, 0, 0, WHITE);
+            Sprite::new(&plain, 8, 1).draw(&mut gfx, 0, 0, 1, WHITE);
+            for i in 0..8 {
+                let set = bitmap[0] & (0x80 >> i) != 0;
+                // The mask draws set and clear bits, outside it the background stays
+                let expected = if i < 4 { if set { WHITE } else { BLACK } } else { background };
+                assert_eq!(gfx.get_pixel(i, 0), expected, "masked x {} on {}", i, background);
+                // Without a mask only set bits are drawn
+                assert_eq!(gfx.get_pixel(i, 1), if set { WHITE } else { background }, "plain x {} on {}", i, background);
+            }
+        }
+    }
+
+    #[test]
+    fn dirty_rect_covers_the_old_and_new_position() {
+        let mut gfx: AdafruitGFX = AdafruitGFX::new(128, 64);
+        let mut clock = TestClock { now: ClockInstant::from_ticks(0) };
+        let mut animation = Animation::new(Sprite::new(&FRAMES, 8, 2), 10, 10, PlayMode::Loop);
+        animation.bg = Some(BLACK);
+        animation.tick(&mut gfx, &mut clock);
+        assert_eq!(gfx.get_pixel(10, 10), WHITE);
+
+        animation.move_to(20, 15);
+        assert_eq!(animation.tick(&mut gfx, &mut clock), Some(Rect::new(10, 10, 18, 7)));
+        assert_eq!(animation.position(), (20, 15));
+        // bg erased the old position before the frame was drawn at the new one
+        assert_eq!(gfx.get_pixel(10, 10), BLACK);
+        assert_eq!(gfx.get_pixel(20, 15), WHITE);
+
+        // Moving back to the same place changes nothing
+        animation.move_to(20, 15);
+        assert_eq!(animation.tick(&mut gfx, &mut clock), None);
+    }
+}
@@ -0,0 +1,369 @@
+/*
+ *  Typed SSD1306 commands.
+ *  Every command of the SSD1306 datasheet (rev 1.5) together with its arguments,
+ *  serialised to the bytes that follow the 0x00 command control byte.
+ *  Out of range arguments are masked or clamped to the nearest valid value.
+ */
+
+use crate::adafruit_ssd1306::SSD1306Commands::*;
+
+/// Memory addressing mode (command 0x20)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AddressingMode {
+    Horizontal = 0x00,
+    Vertical = 0x01,
+    Page = 0x02,
+}
+
+/// VCOMH deselect level (command 0xDB)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VcomhLevel {
+    V065,       // ~0.65 x Vcc
+    V077,       // ~0.77 x Vcc (reset value)
+    V083,       // ~0.83 x Vcc
+    Raw(u8),    // undocumented levels, e.g. 0x40 used by the Adafruit init
+}
+
+impl VcomhLevel {
+    pub fn value(&self) -> u8 {
+        match self {
+            VcomhLevel::V065 => 0x00,
+            VcomhLevel::V077 => 0x20,
+            VcomhLevel::V083 => 0x30,
+            VcomhLevel::Raw(v) => *v & 0x70,
+        }
+    }
+}
+
+/// Direction of the continuous hardware scroll
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScrollDirection {
+    Right,
+    Left,
+}
+
+/// Time between scroll steps, in frames
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScrollInterval {
+    Frames2,
+    Frames3,
+    Frames4,
+    Frames5,
+    Frames25,
+    Frames64,
+    Frames128,
+    Frames256,
+}
+
+impl ScrollInterval {
+    pub fn value(&self) -> u8 {
+        match self {
+            ScrollInterval::Frames5 => 0b000,
+            ScrollInterval::Frames64 => 0b001,
+            ScrollInterval::Frames128 => 0b010,
+            ScrollInterval::Frames256 => 0b011,
+            ScrollInterval::Frames3 => 0b100,
+            ScrollInterval::Frames4 => 0b101,
+            ScrollInterval::Frames25 => 0b110,
+            ScrollInterval::Frames2 => 0b111,
+        }
+    }
+}
+
+/// SSD1315 charge pump output voltage (command 0x8D)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PumpVoltage {
+    V6_0 = 0x15,
+    V7_5 = 0x14,
+    V8_5 = 0x94,
+    V9_0 = 0x95,
+}
+
+/// Fade out / blink behaviour (command 0x23)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FadeMode {
+    Disabled = 0x00,
+    FadeOut = 0x20,
+    Blink = 0x30,
+}
+
+/// Frames between two contrast steps of the fade out / blink (command 0x23)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FadeInterval {
+    Frames8 = 0,
+    Frames16,
+    Frames24,
+    Frames32,
+    Frames40,
+    Frames48,
+    Frames56,
+    Frames64,
+    Frames72,
+    Frames80,
+    Frames88,
+    Frames96,
+    Frames104,
+    Frames112,
+    Frames120,
+    Frames128,
+}
+
+/// One SSD1306 command with its arguments
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Command {
+    // Fundamental
+    Contrast(u8),
+    EntireDisplayOn(bool),      // true lights every pixel, false follows the RAM
+    Invert(bool),
+    DisplayOn(bool),
+
+    // Scrolling
+    HorizontalScroll { direction: ScrollDirection, start_page: u8, interval: ScrollInterval, end_page: u8, columns: Option<(u8, u8)> },
+    DiagonalScroll { direction: ScrollDirection, start_page: u8, interval: ScrollInterval, end_page: u8, vertical_offset: u8 },
+    DeactivateScroll,
+    ActivateScroll,
+    VerticalScrollArea { fixed_rows: u8, scroll_rows: u8 },
+
+    // Addressing
+    ColumnStart(u8),            // page addressing mode: lower and upper nibble of the start column
+    AddressingMode(AddressingMode),
+    ColumnAddress { start: u8, end: u8 },  // RAM columns, up to 131 on 132 column controllers, see Controller::column_address()
+    PageAddress { start: u8, end: u8 },
+    PageStart(u8),              // page addressing mode: page 0-7
+
+    // Hardware configuration
+    StartLine(u8),
+    SegmentRemap(bool),         // true maps column 127 to SEG0
+    Multiplex(u8),              // number of active rows, 16-64
+    ComScanReverse(bool),       // true scans from COM[N-1] to COM0
+    DisplayOffset(u8),
+    ComPins { alternative: bool, left_right_remap: bool },
+
+    // Timing and driving
+    ClockDiv { divide: u8, oscillator: u8 },   // divide ratio 1-16, oscillator frequency 0-15
+    Precharge { phase1: u8, phase2: u8 },       // DCLKs, 1-15 each
+    VcomhDeselect(VcomhLevel),
+    ChargePump(bool),
+    ChargePumpVoltage(PumpVoltage), // SSD1315 only: enable the pump at a given voltage
+    DcDc(bool),                 // SH1106 only: internal DC-DC converter
+    ExternalVcc,                // SSD1305 only: master configuration selecting the external supply
+    AreaColour { colour: bool, low_power: bool }, // SSD1305 only
+    LookupTable([u8; 4]),       // SSD1305 only: pulse widths of bank 0 and colours A-C, 31-63 DCLKs
+    Nop,
+
+    // Advanced graphics
+    FadeBlink(FadeMode, FadeInterval),
+    ZoomIn(bool),
+}
+
+/// Serialised command, at most 7 bytes
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CommandBytes {
+    buf: [u8; 8],
+    len: usize,
+}
+
+impl CommandBytes {
+    fn new(bytes: &[u8]) -> Self {
+        let mut buf = [0; 8];
+        buf[..bytes.len()].copy_from_slice(bytes);
+        Self { buf, len: bytes.len() }
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+}
+
+impl Command {
+    /// The bytes to send after the 0x00 command control byte
+    pub fn bytes(&self) -> CommandBytes {
+        match *self {
+            Command::Contrast(c) => CommandBytes::new(&[SSD1306_SETCONTRAST as u8, c]),
+            Command::EntireDisplayOn(on) => CommandBytes::new(&[
+                if on { SSD1306_DISPLAYALLON as u8 } else { SSD1306_DISPLAYALLON_RESUME as u8 },
+            ]),
+            Command::Invert(i) => CommandBytes::new(&[
+                if i { SSD1306_INVERTDISPLAY as u8 } else { SSD1306_NORMALDISPLAY as u8 },
+            ]),
+            Command::DisplayOn(on) => CommandBytes::new(&[
+                if on { SSD1306_DISPLAYON as u8 } else { SSD1306_DISPLAYOFF as u8 },
+            ]),
+
+            Command::HorizontalScroll { direction, start_page, interval, end_page, columns } => {
+                // Without a column range the last two bytes are the SSD1306 dummies 0x00, 0xFF
+                let (start_column, end_column) = columns.unwrap_or((0x00, 0xff));
+                CommandBytes::new(&[
+                    match direction {
+                        ScrollDirection::Right => SSD1306_HORIZONTAL_SCROLL_R as u8,
+                        ScrollDirection::Left => SSD1306_HORIZONTAL_SCROLL_L as u8,
+                    },
+                    0x00,               // dummy
+                    start_page & 0x07,
+                    interval.value(),
+                    end_page & 0x07,
+                    start_column,
+                    end_column,
+                ])
+            }
+            Command::DiagonalScroll { direction, start_page, interval, end_page, vertical_offset } => CommandBytes::new(&[
+                match direction {
+                    ScrollDirection::Right => SSD1306_VERTICAL_R_SCROLL as u8,
+                    ScrollDirection::Left => SSD1306_VERTICAL_L_SCROLL as u8,
+                },
+                0x00,               // dummy
+                start_page & 0x07,
+                interval.value(),
+                end_page & 0x07,
+                vertical_offset & 0x3f,
+            ]),
+            Command::DeactivateScroll => CommandBytes::new(&[SSD1306_DEACTIVATE_SCROLL as u8]),
+            Command::ActivateScroll => CommandBytes::new(&[SSD1306_ACTIVATE_SCROLL as u8]),
+            Command::VerticalScrollArea { fixed_rows, scroll_rows } => CommandBytes::new(&[
+                SSD1306_VERTICAL_SCROLL_AREA as u8,
+                fixed_rows & 0x3f,
+                scroll_rows & 0x7f,
+            ]),
+
+            Command::ColumnStart(c) => CommandBytes::new(&[
+                (SSD1306_SETLOWCOLUMN as u8) | (c & 0x0f),
+                (SSD1306_SETHIGHCOLUMN as u8) | ((c >> 4) & 0x0f),
+            ]),
+            Command::AddressingMode(mode) => CommandBytes::new(&[SSD1306_MEMORYMODE as u8, mode as u8]),
+            Command::ColumnAddress { start, end } => CommandBytes::new(&[
+                SSD1306_COLUMNADDR as u8,
+                start,
+                end,
+            ]),
+            Command::PageAddress { start, end } => CommandBytes::new(&[
+                SSD1306_PAGEADDR as u8,
+                start & 0x07,
+                end & 0x07,
+            ]),
+            Command::PageStart(page) => CommandBytes::new(&[(SSD1306_PAGESTART as u8) | (page & 0x07)]),
+
+            Command::StartLine(line) => CommandBytes::new(&[(SSD1306_SETSTARTLINE as u8) | (line & 0x3f)]),
+            Command::SegmentRemap(remap) => CommandBytes::new(&[(SSD1306_SEGREMAP as u8) | (remap as u8)]),
+            Command::Multiplex(rows) => CommandBytes::new(&[
+                SSD1306_SETMULTIPLEX as u8,
+                rows.clamp(16, 64) - 1,
+            ]),
+            Command::ComScanReverse(reverse) => CommandBytes::new(&[
+                if reverse { SSD1306_COMSCANDEC as u8 } else { SSD1306_COMSCANINC as u8 },
+            ]),
+            Command::DisplayOffset(offset) => CommandBytes::new(&[SSD1306_SETDISPLAYOFFSET as u8, offset & 0x3f]),
+            Command::ComPins { alternative, left_right_remap } => CommandBytes::new(&[
+                SSD1306_SETCOMPINS as u8,
+                0x02 | ((alternative as u8) << 4) | ((left_right_remap as u8) << 5),
+            ]),
+
+            Command::ClockDiv { divide, oscillator } => CommandBytes::new(&[
+                SSD1306_SETDISPLAYCLOCKDIV as u8,
+                ((oscillator & 0x0f) << 4) | (divide.clamp(1, 16) - 1),
+            ]),
+            Command::Precharge { phase1, phase2 } => CommandBytes::new(&[
+                SSD1306_SETPRECHARGE as u8,
+                (phase2.clamp(1, 15) << 4) | phase1.clamp(1, 15),
+            ]),
+            Command::VcomhDeselect(level) => CommandBytes::new(&[SSD1306_SETVCOMDETECT as u8, level.value()]),
+            Command::ChargePump(on) => CommandBytes::new(&[
+                SSD1306_CHARGEPUMP as u8,
+                if on { 0x14 } else { 0x10 },
+            ]),
+            Command::ChargePumpVoltage(voltage) => CommandBytes::new(&[SSD1306_CHARGEPUMP as u8, voltage as u8]),
+            Command::DcDc(on) => CommandBytes::new(&[
+                SH1106_SETDCDC as u8,
+                if on { 0x8b } else { 0x8a },
+            ]),
+            Command::ExternalVcc => CommandBytes::new(&[SH1106_SETDCDC as u8, 0x8e]),
+            Command::AreaColour { colour, low_power } => CommandBytes::new(&[
+                SSD1305_SETAREACOLOUR as u8,
+                (if colour { 0x30 } else { 0x00 }) | (if low_power { 0x05 } else { 0x00 }),
+            ]),
+            Command::LookupTable(widths) => CommandBytes::new(&[
+                SSD1305_SETLUT as u8,
+                widths[0].clamp(31, 63),
+                widths[1].clamp(31, 63),
+                widths[2].clamp(31, 63),
+                widths[3].clamp(31, 63),
+            ]),
+            Command::Nop => CommandBytes::new(&[SSD1306_NOP as u8]),
+
+            Command::FadeBlink(mode, interval) => CommandBytes::new(&[
+                SSD1306_FADEBLINK as u8,
+                (mode as u8) | (interval as u8),
+            ]),
+            Command::ZoomIn(on) => CommandBytes::new(&[SSD1306_ZOOMIN as u8, on as u8]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn horizontal_scroll_without_columns_sends_dummies() {
+        let cmd = Command::HorizontalScroll {
+            direction: ScrollDirection::Right,
+            start_page: 0,
+            interval: ScrollInterval::Frames5,
+            end_page: 0x0f,
+            columns: None,
+        };
+        assert_eq!(cmd.bytes().as_slice(), &[0x26, 0x00, 0x00, 0x00, 0x07, 0x00, 0xff]);
+    }
+
+    #[test]
+    fn horizontal_scroll_with_columns() {
+        let cmd = Command::HorizontalScroll {
+            direction: ScrollDirection::Left,
+            start_page: 2,
+            interval: ScrollInterval::Frames2,
+            end_page: 5,
+            columns: Some((16, 111)),
+        };
+        assert_eq!(cmd.bytes().as_slice(), &[0x27, 0x00, 0x02, 0x07, 0x05, 16, 111]);
+    }
+
+    #[test]
+    fn diagonal_scroll_masks_offset() {
+        let cmd = Command::DiagonalScroll {
+            direction: ScrollDirection::Left,
+            start_page: 1,
+            interval: ScrollInterval::Frames256,
+            end_page: 6,
+            vertical_offset: 0x41,
+        };
+        assert_eq!(cmd.bytes().as_slice(), &[0x2a, 0x00, 0x01, 0x03, 0x06, 0x01]);
+        assert_eq!(Command::VerticalScrollArea { fixed_rows: 8, scroll_rows: 24 }.bytes().as_slice(), &[0xa3, 8, 24]);
+    }
+
+    #[test]
+    fn clock_div_packs_oscillator_and_ratio() {
+        assert_eq!(Command::ClockDiv { divide: 1, oscillator: 8 }.bytes().as_slice(), &[0xd5, 0x80]);
+        assert_eq!(Command::ClockDiv { divide: 16, oscillator: 15 }.bytes().as_slice(), &[0xd5, 0xff]);
+        assert_eq!(Command::ClockDiv { divide: 0, oscillator: 0x1f }.bytes().as_slice(), &[0xd5, 0xf0]);
+    }
+
+    #[test]
+    fn precharge_packs_both_phases() {
+        assert_eq!(Command::Precharge { phase1: 1, phase2: 15 }.bytes().as_slice(), &[0xd9, 0xf1]);
+        assert_eq!(Command::Precharge { phase1: 2, phase2: 2 }.bytes().as_slice(), &[0xd9, 0x22]);
+        assert_eq!(Command::Precharge { phase1: 0, phase2: 20 }.bytes().as_slice(), &[0xd9, 0xf1]);
+    }
+
+    #[test]
+    fn multiplex_is_clamped() {
+        assert_eq!(Command::Multiplex(64).bytes().as_slice(), &[0xa8, 63]);
+        assert_eq!(Command::Multiplex(32).bytes().as_slice(), &[0xa8, 31]);
+        assert_eq!(Command::Multiplex(0).bytes().as_slice(), &[0xa8, 15]);
+        assert_eq!(Command::Multiplex(200).bytes().as_slice(), &[0xa8, 63]);
+    }
+
+    #[test]
+    fn column_start_splits_nibbles() {
+        assert_eq!(Command::ColumnStart(0x42).bytes().as_slice(), &[0x02, 0x14]);
+        assert_eq!(Command::PageStart(3).bytes().as_slice(), &[0xb3]);
+    }
+}
@@ -0,0 +1,248 @@
+/*
+ *  One Drawable canvas spread over several displays, e.g. two 128x64 panels side by
+ *  side as a 256x64 canvas. Every pixel is routed to the tile covering it and drawn
+ *  into that display's AdafruitGFX, which applies the tile's own rotation.
+ */
+
+use crate::adafruit_gfx_h::{ AdafruitGFX, Drawable, BLACK, WHITE };
+use crate::adafruit_ssd1306::MyGpioPin;
+use crate::adafruit_ssd1306_h::{ AdafruitSSD1306, Display };
+use embedded_hal::delay::DelayNs;
+use embedded_hal::i2c::I2c;
+
+/// A display that can be part of a TiledDisplay, B is the size of its buffer
+pub trait TileTarget<const B: usize = 1024> {
+    fn gfx(&mut self) -> &mut AdafruitGFX<B>;
+    fn show(&mut self);
+}
+
+impl<I2C, GPIO, DELAY, E> TileTarget for AdafruitSSD1306<I2C, GPIO, DELAY>
+    where
+        DELAY: DelayNs,
+        GPIO: MyGpioPin,
+        I2C: I2c<Error = E>
+{
+    fn gfx(&mut self) -> &mut AdafruitGFX {
+        &mut self.gfx
+    }
+
+    fn show(&mut self) {
+        Display::show(self);
+    }
+}
+
+/// One display and the canvas position of its top left corner
+pub struct Tile<'a, const B: usize = 1024> {
+    pub target: &'a mut dyn TileTarget<B>,
+    pub x: i16,
+    pub y: i16,
+}
+
+impl<'a, const B: usize> Tile<'a, B> {
+    /// `rotation` is applied to the display's AdafruitGFX, so a panel mounted sideways
+    /// covers a rotated width x height area of the canvas
+    pub fn new(target: &'a mut dyn TileTarget<B>, x: i16, y: i16, rotation: u8) -> Self {
+        target.gfx().set_rotation(rotation);
+        Tile { target, x, y }
+    }
+}
+
+/// N tiles whose displays have B byte buffers
+pub struct TiledDisplay<'a, const N: usize, const B: usize = 1024> {
+    pub tiles: [Tile<'a, B>; N],
+    pub raw_width: i16,     // bounding box of the tiles - never changes
+    pub raw_height: i16,
+    pub width: i16,         // dependent on rotation
+    pub height: i16,
+    pub cursor_x: i16,
+    pub cursor_y: i16,
+    pub textcolor: u8,
+    pub textbgcolor: u8,
+    pub textsize: i16,
+    pub rotation: u8,
+    pub wrap: bool,
+}
+
+impl<'a, const N: usize, const B: usize> TiledDisplay<'a, N, B> {
+    pub fn new(mut tiles: [Tile<'a, B>; N]) -> Self {
+        let mut raw_width = 0;
+        let mut raw_height = 0;
+        for tile in tiles.iter_mut() {
+            let gfx = tile.target.gfx();
+            raw_width = core::cmp::max(raw_width, tile.x + gfx.width);
+            raw_height = core::cmp::max(raw_height, tile.y + gfx.height);
+        }
+        TiledDisplay {
+            tiles,
+            raw_width,
+            raw_height,
+            width: raw_width,
+            height: raw_height,
+            cursor_x: 0,
+            cursor_y: 0,
+            textcolor: WHITE,
+            textbgcolor: BLACK,
+            textsize: 1,
+            rotation: 0,
+            wrap: true,
+        }
+    }
+
+    /// Clear every tile's buffer
+    pub fn clear_display(&mut self) {
+        for tile in self.tiles.iter_mut() {
+            tile.target.gfx().buffer.fill(0);
+        }
+    }
+
+    /// Flush the tiles one after the other
+    pub fn show(&mut self) {
+        for tile in self.tiles.iter_mut() {
+            tile.target.show();
+        }
+    }
+}
+
+impl<const N: usize, const B: usize> Drawable for TiledDisplay<'_, N, B> {
+    fn draw_pixel(&mut self, x: i16, y: i16, color: u8) {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return;
+        }
+        let (x, y) = match self.rotation {
+            1 => (self.raw_width - y - 1, x),
+            2 => (self.raw_width - x - 1, self.raw_height - y - 1),
+            3 => (y, self.raw_height - x - 1),
+            _ => (x, y),
+        };
+        for tile in self.tiles.iter_mut() {
+            let gfx = tile.target.gfx();
+            let (tx, ty) = (x - tile.x, y - tile.y);
+            if tx >= 0 && ty >= 0 && tx < gfx.width && ty < gfx.height {
+                gfx.draw_pixel(tx, ty, color);
+                return;
+            }
+        }
+    }
+
+    fn write_char(&mut self, c: char) -> u8 {
+        if c == '\n' {
+            self.cursor_y += self.textsize * 8;
+            self.cursor_x = 0;
+        } else if c == '\r' {
+            self.cursor_x = 0;
+        } else {
+            self.draw_char(self.cursor_x, self.cursor_y, c as u8, self.textcolor, self.textbgcolor, self.textsize);
+            self.cursor_x += self.textsize * 6;
+            if self.wrap && self.cursor_x > self.width - self.textsize * 6 {
+                self.cursor_y += self.textsize * 8;
+                self.cursor_x = 0;
+            }
+        }
+        1
+    }
+
+    fn write_string(&mut self, value: &str) {
+        for chr in value.chars() {
+            self.write_char(chr);
+        }
+    }
+
+    fn get_width(&self) -> i16 {
+        self.width
+    }
+
+    fn get_height(&self) -> i16 {
+        self.height
+    }
+
+    fn set_text_cursor(&mut self, x: i16, y: i16) {
+        self.cursor_x = x;
+        self.cursor_y = y;
+    }
+
+    fn set_text_size(&mut self, s: i16) {
+        self.textsize = if s > 0 { s } else { 1 };
+    }
+
+    fn set_text_color(&mut self, c: u8) {
+        self.textcolor = c;
+        self.textbgcolor = c;
+    }
+
+    fn set_text_color_independent(&mut self, c: u8, b: u8) {
+        self.textcolor = c;
+        self.textbgcolor = b;
+    }
+
+    fn set_text_wrap(&mut self, w: bool) {
+        self.wrap = w;
+    }
+
+    // Rotates the whole canvas, on top of the rotation of each tile
+    fn set_rotation(&mut self, r: u8) {
+        self.rotation = r % 4;
+        (self.width, self.height) = match self.rotation {
+            1 | 3 => (self.raw_height, self.raw_width),
+            _ => (self.raw_width, self.raw_height),
+        };
+    }
+
+    fn get_rotation(&mut self) -> u8 {
+        self.rotation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controller::Controller;
+    use crate::mock;
+
+    #[test]
+    fn pixels_are_routed_across_the_seam() {
+        let mut left = mock::display(128, 64, Controller::Ssd1306);
+        let mut right = mock::display(128, 64, Controller::Ssd1306);
+        {
+            let mut tiled = TiledDisplay::new([Tile::new(&mut left, 0, 0, 0), Tile::new(&mut right, 128, 0, 0)]);
+            assert_eq!((tiled.get_width(), tiled.get_height()), (256, 64));
+            tiled.draw_fast_h_line(120, 10, 16, WHITE);
+            tiled.draw_pixel(256, 10, WHITE); // off the canvas
+        }
+        for x in 0..128 {
+            assert_eq!(left.gfx.get_pixel(x, 10), if x >= 120 { WHITE } else { BLACK }, "left x {}", x);
+            assert_eq!(right.gfx.get_pixel(x, 10), if x < 8 { WHITE } else { BLACK }, "right x {}", x);
+        }
+    }
+
+    #[test]
+    fn each_tile_keeps_its_rotation() {
+        let mut left = mock::display(128, 64, Controller::Ssd1306);
+        let mut sideways = mock::display(128, 64, Controller::Ssd1306);
+        {
+            // The second panel is mounted sideways and covers 64x128 of the canvas
+            let mut tiled = TiledDisplay::new([Tile::new(&mut left, 0, 0, 0), Tile::new(&mut sideways, 128, 0, 1)]);
+            assert_eq!((tiled.get_width(), tiled.get_height()), (192, 128));
+            tiled.draw_pixel(128, 0, WHITE);
+            tiled.draw_pixel(191, 127, WHITE);
+        }
+        assert!(left.gfx.buffer.iter().all(|&b| b == 0));
+        // Rotation 1 maps (x, y) to raw (raw_width - y - 1, x)
+        assert_eq!(sideways.gfx.rotation, 1);
+        assert_eq!(sideways.gfx.buffer[127], 0x01);
+        assert_eq!(sideways.gfx.buffer[7 * 128], 0x80);
+        assert_eq!(sideways.gfx.buffer.iter().filter(|&&b| b != 0).count(), 2);
+    }
+
+    #[test]
+    fn show_flushes_every_tile_to_its_display() {
+        let mut left = mock::display(128, 64, Controller::Ssd1306);
+        let mut right = mock::display(128, 64, Controller::Ssd1306);
+        {
+            let mut tiled = TiledDisplay::new([Tile::new(&mut left, 0, 0, 0), Tile::new(&mut right, 128, 0, 0)]);
+            tiled.fill_rect(0, 0, 128, 64, WHITE);
+            tiled.show();
+        }
+        assert_eq!(left.i2c.data(0x3c), [0xff; 1024]);
+        assert_eq!(right.i2c.data(0x3c), [0x00; 1024]);
+    }
+}
@@ -0,0 +1,171 @@
+/*
+ *  Non-blocking frame transfer.
+ *  FrameFlusher copies the display buffer into one of two 0x40-prefixed frames and
+ *  hands it to a FrameTransfer (typically I2C with DMA), so show() returns while the
+ *  frame is on the bus and the application can draw the next one.
+ *  BlockingTransfer is the fallback for buses without DMA: it sends the frame at once.
+ */
+
+use crate::adafruit_ssd1306::MyGpioPin;
+use crate::adafruit_ssd1306_h::AdafruitSSD1306;
+use embedded_hal::delay::DelayNs;
+use embedded_hal::i2c::{ ErrorType, I2c, Operation };
+
+/// Data control byte followed by the largest buffer (128x64)
+pub const FRAME_BYTES: usize = 1025;
+
+pub type Frame = [u8; FRAME_BYTES];
+
+/// Bus able to send a frame in the background.
+/// The frame is 'static so it stays valid while DMA reads it
+pub trait FrameTransfer {
+    /// Start sending frame[..len] to `address`
+    fn start(&mut self, address: u8, frame: &'static mut Frame, len: usize);
+    /// The frame of the last transfer once it is done, None while it is still on the bus
+    fn poll(&mut self) -> Option<&'static mut Frame>;
+}
+
+/// FrameTransfer over a blocking I2C bus, start() only returns when the frame is sent
+pub struct BlockingTransfer<I2C> {
+    pub i2c: I2C,
+    done: Option<&'static mut Frame>,
+}
+
+impl<I2C> BlockingTransfer<I2C> {
+    pub fn new(i2c: I2C) -> Self {
+        BlockingTransfer { i2c, done: None }
+    }
+}
+
+impl<I2C: I2c> FrameTransfer for BlockingTransfer<I2C> {
+    fn start(&mut self, address: u8, frame: &'static mut Frame, len: usize) {
+        let _ = self.i2c.write(address, &frame[..len]);
+        self.done = Some(frame);
+    }
+
+    fn poll(&mut self) -> Option<&'static mut Frame> {
+        self.done.take()
+    }
+}
+
+// The driver keeps sending its commands through the wrapped bus
+impl<I2C: I2c> ErrorType for BlockingTransfer<I2C> {
+    type Error = I2C::Error;
+}
+
+impl<I2C: I2c> I2c for BlockingTransfer<I2C> {
+    fn transaction(&mut self, address: u8, operations: &mut [Operation<'_>]) -> Result<(), Self::Error> {
+        self.i2c.transaction(address, operations)
+    }
+}
+
+/// Double buffered flush through the FrameTransfer of the display's bus
+pub struct FrameFlusher {
+    back: Option<&'static mut Frame>,   // frame filled by the next show()
+    spare: Option<&'static mut Frame>,  // the other frame, when it is not on the bus
+    in_flight: bool,
+}
+
+impl FrameFlusher {
+    pub fn new(first: &'static mut Frame, second: &'static mut Frame) -> Self {
+        FrameFlusher { back: Some(first), spare: Some(second), in_flight: false }
+    }
+
+    /// Copy the display buffer and start sending it. Only waits when the previous
+    /// frame is still on the bus, after the copy
+    pub fn show<I2C, GPIO, DELAY, E>(&mut self, display: &mut AdafruitSSD1306<I2C, GPIO, DELAY>)
+        where
+            DELAY: DelayNs,
+            GPIO: MyGpioPin,
+            I2C: I2c<Error = E> + FrameTransfer
+    {
+        let frame = match self.back.take() {
+            Some(frame) => frame,
+            None => return,
+        };
+        let len = (display.gfx.raw_width as usize) * (display.gfx.raw_height as usize) / 8;
+        frame[0] = 0x40; // Data Mode
+        frame[1..=len].copy_from_slice(&display.gfx.buffer[..len]);
+
+        self.wait_flush(display);
+        display.set_frame_window();
+        display.i2c.start(display.address, frame, len + 1);
+        self.in_flight = true;
+        self.back = self.spare.take();
+    }
+
+    pub fn is_flush_complete<I2C, GPIO, DELAY>(&mut self, display: &mut AdafruitSSD1306<I2C, GPIO, DELAY>) -> bool
+        where I2C: FrameTransfer
+    {
+        if self.in_flight {
+            if let Some(frame) = display.i2c.poll() {
+                self.spare = Some(frame);
+                self.in_flight = false;
+            }
+        }
+        !self.in_flight
+    }
+
+    /// Block until the frame on the bus is sent, needed before other commands use the bus
+    pub fn wait_flush<I2C, GPIO, DELAY>(&mut self, display: &mut AdafruitSSD1306<I2C, GPIO, DELAY>)
+        where I2C: FrameTransfer
+    {
+        while !self.is_flush_complete(display) {
+            core::hint::spin_loop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use super::*;
+    use crate::adafruit_gfx_h::AdafruitGFX;
+    use crate::adafruit_ssd1306_h::{ Display, SSD1306_I2C_ADDRESS };
+    use crate::controller::Controller;
+    use crate::mock::{ MockI2c, MockPin, NoDelay };
+    use std::boxed::Box;
+    use std::vec::Vec;
+
+    fn frame() -> &'static mut Frame {
+        Box::leak(Box::new([0; FRAME_BYTES]))
+    }
+
+    fn back_frame(flusher: &FrameFlusher) -> Option<*const Frame> {
+        flusher.back.as_deref().map(|frame| frame as *const Frame)
+    }
+
+    #[test]
+    fn blocking_transfer_rotates_the_frames() {
+        let i2c = BlockingTransfer::new(MockI2c::new(&[SSD1306_I2C_ADDRESS]));
+        let mut display = AdafruitSSD1306::new(i2c, SSD1306_I2C_ADDRESS, MockPin::default(), NoDelay, AdafruitGFX::new(128, 64), Controller::Ssd1306);
+        let (first, second) = (frame(), frame());
+        let frames = [first as *const Frame, second as *const Frame];
+        let mut flusher = FrameFlusher::new(first, second);
+
+        let mut sent = Vec::new();
+        for n in 0..4 {
+            display.gfx.buffer.fill(n + 1);
+            sent.extend_from_slice(&display.gfx.buffer);
+            assert_eq!(back_frame(&flusher), Some(frames[n as usize % 2]));
+            flusher.show(&mut display);
+            // The other frame is filled next, this one is still owned by the transfer
+            assert_eq!(back_frame(&flusher), Some(frames[(n as usize + 1) % 2]));
+            assert!(flusher.spare.is_none());
+            if n % 2 == 0 {
+                // show() waits by itself when the frame was not polled back
+                continue;
+            }
+            assert!(flusher.is_flush_complete(&mut display));
+            assert!(flusher.spare.is_some());
+        }
+        flusher.wait_flush(&mut display);
+        assert!(flusher.is_flush_complete(&mut display));
+        assert!(flusher.back.is_some() && flusher.spare.is_some());
+
+        // Every frame went out whole, after its window
+        let bus = &display.i2c.i2c;
+        assert_eq!(bus.data(SSD1306_I2C_ADDRESS), sent);
+        assert_eq!(bus.commands(SSD1306_I2C_ADDRESS).iter().filter(|c| c[0] == 0x21).count(), 4);
+    }
+}
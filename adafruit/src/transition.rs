@@ -0,0 +1,191 @@
+/*
+ *  Screen transitions between two buffers.
+ *  Each call to Transition::next() renders one intermediate frame into the output
+ *  buffer (usually the display's gfx), the application shows it and calls again
+ *  at its own pace until next() returns false.
+ */
+
+use crate::adafruit_gfx_h::{ AdafruitGFX, Drawable };
+pub use crate::dither::BAYER_8X8;  // moved to dither, transition::BAYER_8X8 keeps working
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TransitionKind {
+    Wipe,       // the new screen is uncovered from left to right
+    SlideLeft,  // the new screen comes in from the right, pushing the old one out
+    SlideRight, // the new screen comes in from the left
+    PushUp,     // the new screen comes in from the bottom
+    PushDown,   // the new screen comes in from the top
+    Dissolve,   // pixels switch over in Bayer order
+}
+
+pub struct Transition {
+    pub kind: TransitionKind,
+    pub steps: u16,
+    step: u16,
+}
+
+impl Transition {
+    /// A transition over `steps` frames, the last one is the new screen
+    pub fn new(kind: TransitionKind, steps: u16) -> Self {
+        Transition { kind, steps: steps.max(1), step: 0 }
+    }
+
+    pub fn restart(&mut self) {
+        self.step = 0;
+    }
+
+    pub fn finished(&self) -> bool {
+        self.step >= self.steps
+    }
+
+    /// Render the next frame from `from` and `to` into `out`, all three of the same size.
+    /// Returns false, without drawing, once the transition is complete
+    pub fn next<const N: usize>(&mut self, from: &AdafruitGFX<N>, to: &AdafruitGFX<N>, out: &mut AdafruitGFX<N>) -> bool {
+        if self.finished() {
+            return false;
+        }
+        self.step += 1;
+        self.render(self.step, from, to, out);
+        true
+    }
+
+    /// Render frame `step` (0 = `from`, steps = `to`) into `out`
+    pub fn render<const N: usize>(&self, step: u16, from: &AdafruitGFX<N>, to: &AdafruitGFX<N>, out: &mut AdafruitGFX<N>) {
+        let (w, h) = (out.width as i32, out.height as i32);
+        let step = step.min(self.steps) as i32;
+        let steps = self.steps as i32;
+        let dx = (w * step / steps) as i16;
+        let dy = (h * step / steps) as i16;
+        let level = (64 * step / steps) as u8;
+        let (w, h) = (w as i16, h as i16);
+
+        for y in 0..h {
+            for x in 0..w {
+                let color = match self.kind {
+                    TransitionKind::Wipe => {
+                        if x < dx { to.get_pixel(x, y) } else { from.get_pixel(x, y) }
+                    }
+                    TransitionKind::SlideLeft => {
+                        if x + dx < w { from.get_pixel(x + dx, y) } else { to.get_pixel(x + dx - w, y) }
+                    }
+                    TransitionKind::SlideRight => {
+                        if x < dx { to.get_pixel(x - dx + w, y) } else { from.get_pixel(x - dx, y) }
+                    }
+                    TransitionKind::PushUp => {
+                        if y + dy < h { from.get_pixel(x, y + dy) } else { to.get_pixel(x, y + dy - h) }
+                    }
+                    TransitionKind::PushDown => {
+                        if y < dy { to.get_pixel(x, y - dy + h) } else { from.get_pixel(x, y - dy) }
+                    }
+                    TransitionKind::Dissolve => {
+                        if BAYER_8X8[(y % 8) as usize][(x % 8) as usize] < level { to.get_pixel(x, y) } else { from.get_pixel(x, y) }
+                    }
+                };
+                out.draw_pixel(x, y, color);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adafruit_gfx_h::{ BLACK, WHITE };
+
+    const KINDS: [TransitionKind; 6] = [
+        TransitionKind::Wipe,
+        TransitionKind::SlideLeft,
+        TransitionKind::SlideRight,
+        TransitionKind::PushUp,
+        TransitionKind::PushDown,
+        TransitionKind::Dissolve,
+    ];
+
+    fn screen(seed: i16) -> AdafruitGFX {
+        let mut gfx: AdafruitGFX = AdafruitGFX::new(128, 64);
+        for y in 0..64 {
+            for x in 0..128 {
+                if (x * seed + y * (seed + 2) + x / 3) % 5 < 2 {
+                    gfx.draw_pixel(x, y, WHITE);
+                }
+            }
+        }
+        gfx
+    }
+
+    #[test]
+    fn every_kind_goes_from_the_old_to_the_new_screen() {
+        let (from, to) = (screen(1), screen(3));
+        assert_ne!(from.buffer, to.buffer);
+        for kind in KINDS {
+            let mut out: AdafruitGFX = AdafruitGFX::new(128, 64);
+            let mut transition = Transition::new(kind, 4);
+            transition.render(0, &from, &to, &mut out);
+            assert_eq!(out.buffer, from.buffer, "{:?} first step", kind);
+
+            let mut frames = 0;
+            while transition.next(&from, &to, &mut out) {
+                frames += 1;
+            }
+            assert_eq!(frames, 4, "{:?}", kind);
+            assert!(transition.finished());
+            assert_eq!(out.buffer, to.buffer, "{:?} last step", kind);
+        }
+    }
+
+    #[test]
+    fn halfway_boundaries() {
+        let (from, to) = (screen(1), screen(3));
+        let mut out: AdafruitGFX = AdafruitGFX::new(128, 64);
+        let render = |kind, out: &mut AdafruitGFX| Transition::new(kind, 4).render(2, &from, &to, out);
+
+        render(TransitionKind::Wipe, &mut out);
+        for y in 0..64 {
+            assert_eq!(out.get_pixel(63, y), to.get_pixel(63, y), "wipe y {}", y);
+            assert_eq!(out.get_pixel(64, y), from.get_pixel(64, y), "wipe y {}", y);
+        }
+
+        // Halfway the old screen's right half meets the new screen's left half
+        render(TransitionKind::SlideLeft, &mut out);
+        for y in 0..64 {
+            assert_eq!(out.get_pixel(63, y), from.get_pixel(127, y), "slide left y {}", y);
+            assert_eq!(out.get_pixel(64, y), to.get_pixel(0, y), "slide left y {}", y);
+        }
+
+        render(TransitionKind::SlideRight, &mut out);
+        for y in 0..64 {
+            assert_eq!(out.get_pixel(63, y), to.get_pixel(127, y), "slide right y {}", y);
+            assert_eq!(out.get_pixel(64, y), from.get_pixel(0, y), "slide right y {}", y);
+        }
+
+        render(TransitionKind::PushUp, &mut out);
+        for x in 0..128 {
+            assert_eq!(out.get_pixel(x, 31), from.get_pixel(x, 63), "push up x {}", x);
+            assert_eq!(out.get_pixel(x, 32), to.get_pixel(x, 0), "push up x {}", x);
+        }
+
+        render(TransitionKind::PushDown, &mut out);
+        for x in 0..128 {
+            assert_eq!(out.get_pixel(x, 31), to.get_pixel(x, 63), "push down x {}", x);
+            assert_eq!(out.get_pixel(x, 32), from.get_pixel(x, 0), "push down x {}", x);
+        }
+    }
+
+    #[test]
+    fn dissolve_switches_pixels_in_bayer_order() {
+        let from: AdafruitGFX = AdafruitGFX::new(128, 64);
+        let mut to: AdafruitGFX = AdafruitGFX::new(128, 64);
+        to.fill_screen(WHITE);
+        let mut out: AdafruitGFX = AdafruitGFX::new(128, 64);
+        Transition::new(TransitionKind::Dissolve, 4).render(2, &from, &to, &mut out);
+
+        // Halfway the pixels of the lower half of the Bayer levels are new
+        for y in 0..64 {
+            for x in 0..128 {
+                let switched = BAYER_8X8[(y % 8) as usize][(x % 8) as usize] < 32;
+                assert_eq!(out.get_pixel(x, y), if switched { WHITE } else { BLACK }, "x {} y {}", x, y);
+            }
+        }
+        assert_eq!(out.buffer.iter().map(|b| b.count_ones()).sum::<u32>(), 128 * 64 / 2);
+    }
+}
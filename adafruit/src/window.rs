@@ -0,0 +1,70 @@
+/*
+ *  Where a flush goes in the controller RAM.
+ *  FlushWindow decides between a single column/page window and page addressing, and
+ *  which commands go before the data, for the blocking and the async driver alike.
+ */
+
+use crate::config::Config;
+use crate::controller::Controller;
+use crate::ssd1306_command::{ AddressingMode, Command };
+use core::ops::{ Range, RangeInclusive };
+
+/// Raw columns first_column..=last_column of pages first_page..=last_page
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FlushWindow {
+    pub controller: Controller,
+    pub first_column: u8,
+    pub last_column: u8,
+    pub first_page: u8,
+    pub last_page: u8,
+    pub offset: u8,             // RAM column of raw column 0
+    pub page_addressing: bool,  // start column and page before every page instead of one window
+}
+
+impl FlushWindow {
+    pub fn new(controller: Controller, config: &Config, first_column: u8, last_column: u8, first_page: u8, last_page: u8) -> Self {
+        FlushWindow {
+            controller,
+            first_column,
+            last_column,
+            first_page,
+            last_page,
+            offset: controller.column_offset() + config.column_offset,
+            page_addressing: controller.page_addressing_only() || config.addressing_mode == AddressingMode::Page,
+        }
+    }
+
+    /// The whole width x height panel
+    pub fn full(controller: Controller, config: &Config, width: i16, height: i16) -> Self {
+        Self::new(controller, config, 0, (width - 1) as u8, 0, (height / 8 - 1) as u8)
+    }
+
+    /// Commands sent once before the data, None with page addressing
+    pub fn window_commands(&self) -> Option<[Command; 2]> {
+        if self.page_addressing {
+            return None;
+        }
+        Some([
+            self.controller.column_address(self.offset + self.first_column, self.offset + self.last_column),
+            Command::PageAddress { start: self.first_page, end: self.last_page },
+        ])
+    }
+
+    /// Commands sent before the data of `page`, only needed with page addressing
+    pub fn page_commands(&self, page: u8) -> Option<[Command; 2]> {
+        if !self.page_addressing {
+            return None;
+        }
+        Some([Command::PageStart(page), Command::ColumnStart(self.offset + self.first_column)])
+    }
+
+    pub fn pages(&self) -> RangeInclusive<u8> {
+        self.first_page..=self.last_page
+    }
+
+    /// Part of a display buffer `width` columns wide holding the data of `page`
+    pub fn buffer_range(&self, page: u8, width: i16) -> Range<usize> {
+        let start = page as usize * width as usize + self.first_column as usize;
+        start..start + (self.last_column - self.first_column) as usize + 1
+    }
+}
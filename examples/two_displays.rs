@@ -0,0 +1,85 @@
+#![no_main]
+#![no_std]
+
+/*
+ * Two SSD1306 displays (0x3C and 0x3D) sharing I2C1 through embedded-hal-bus.
+ * Each display has its own AdafruitGFX buffer and reset pin, the bus can be shared the
+ * same way with other I2C devices (sensors). Use CriticalSectionDevice instead of
+ * RefCellDevice when the bus is also used from interrupts.
+ *
+ * Run with: cargo run --example two_displays
+ */
+
+use core::cell::RefCell;
+use adafruit::{adafruit_gfx_h::{AdafruitGFX, Drawable}, adafruit_ssd1306::{I2cWrapper, OutputPinWrapper}, adafruit_ssd1306_h::{AdafruitSSD1306, Display, SSD1306_I2C_ADDRESS, SSD1306_I2C_ADDRESS_ALT}, config::Config as OledConfig, controller::Controller};
+use cortex_m_rt::entry;
+use embedded_hal::delay::DelayNs;
+use embedded_hal_bus::i2c::RefCellDevice;
+use fugit::HertzU32;
+use stm32f7xx_hal::{gpio::GpioExt, i2c::{BlockingI2c, Mode}, pac, rcc::{HSEClock, HSEClockMode, RccExt, PLLP}};
+use panic_halt as _;
+
+// Each driver owns its delay and there is only one SysTick: busy-wait on the core clock instead
+#[derive(Clone, Copy)]
+struct CycleDelay;
+
+impl DelayNs for CycleDelay {
+    fn delay_ns(&mut self, ns: u32) {
+        // 216 cycles per us at 216 MHz
+        cortex_m::asm::delay(ns / 1000 * 216 + 216);
+    }
+}
+
+#[entry]
+fn main() -> ! {
+    let dp = pac::Peripherals::take().unwrap();
+
+    let mut rcc = dp.RCC.constrain();
+    let clocks = rcc
+        .cfgr
+        .hse(HSEClock::new(HertzU32::MHz(8), HSEClockMode::Oscillator))
+        .sysclk(HertzU32::MHz(216))
+        .pllq(9)
+        .plln(216)
+        .pllp(PLLP::Div2)
+        .pllm(4)
+        .freeze();
+    let gpiob = dp.GPIOB.split();
+    let gpiog = dp.GPIOG.split();
+
+    let sda = gpiob.pb9.into_alternate_open_drain();
+    let scl = gpiob.pb8.into_alternate_open_drain();
+    let bus = RefCell::new(I2cWrapper::new(BlockingI2c::i2c1(dp.I2C1, (scl, sda), Mode::Standard { frequency: HertzU32::kHz(100) }, &clocks, &mut rcc.apb1, 10000)));
+
+    let mut delay = CycleDelay;
+
+    let mut left = AdafruitSSD1306::new(RefCellDevice::new(&bus), SSD1306_I2C_ADDRESS, OutputPinWrapper::new(gpiob.pb11.into_push_pull_output()), CycleDelay, AdafruitGFX::new(128, 64), Controller::Ssd1306);
+    let mut right = AdafruitSSD1306::new(RefCellDevice::new(&bus), SSD1306_I2C_ADDRESS_ALT, OutputPinWrapper::new(gpiog.pg6.into_push_pull_output()), CycleDelay, AdafruitGFX::new(128, 64), Controller::Ssd1306);
+
+    left.begin(&OledConfig::for_panel(128, 64));
+    right.begin(&OledConfig::for_panel(128, 64));
+
+    let mut count: u32 = 0;
+    loop {
+        left.clear_display();
+        left.gfx.set_text_cursor(0, 0);
+        left.gfx.write_string("LEFT 0x3C");
+        left.gfx.draw_rect(0, 16, 128, 48, 1);
+        left.show();
+
+        right.clear_display();
+        right.gfx.set_text_cursor(0, 0);
+        right.gfx.write_string("RIGHT 0x3D");
+        right.gfx.fill_rect(((count * 4) % 128) as i16, 16, 8, 48, 1);
+        right.show();
+
+        count = count.wrapping_add(1);
+        delay.delay_ms(50);
+    }
+}
+
+use stm32f7xx_hal::pac::interrupt;
+#[interrupt]
+fn QUADSPI() {
+    // dummy QUADSPI interrupt handler or you get link error, see src/main.rs
+}
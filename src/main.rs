@@ -16,7 +16,7 @@
 
 // dont forget to run -----  cargo build --target thumbv7em-none-eabihf or have the .cargo folder with config.toml included
 use core::fmt::Write;
-use adafruit::{adafruit_gfx_h::{AdafruitGFX, Drawable}, adafruit_ssd1306::DelayWrapper, adafruit_ssd1306_h::{AdafruitSSD1306, Display}};
+use adafruit::{adafruit_gfx_h::{AdafruitGFX, Drawable}, adafruit_ssd1306::{DelayWrapper, I2cWrapper, OutputPinWrapper, probe}, adafruit_ssd1306_h::{AdafruitSSD1306, Display, SSD1306_I2C_ADDRESS}, config::Config as OledConfig, controller::Controller};
 use cortex_m::delay::Delay;
 use cortex_m_rt::entry;
 use cortex_m_semihosting::hprintln;
@@ -113,20 +113,23 @@ fn main() -> ! {
        let  scl = gpiob.pb8.into_alternate_open_drain(); // SCL pin
        //let  rst = gpiog.pg6.into_push_pull_output(); // SCL pin
 
-        let mut myi2c = BlockingI2c::i2c1(dp.I2C1, (scl,sda), Mode::Standard { frequency: HertzU32::kHz(100) }, &clocks,  &mut rcc.apb1, 10000);
+        let mut myi2c = I2cWrapper::new(BlockingI2c::i2c1(dp.I2C1, (scl,sda), Mode::Standard { frequency: HertzU32::kHz(100) }, &clocks,  &mut rcc.apb1, 10000));
         let mut var_name: [u8; 2] = [0, 2];
         var_name[0]=0xA4;
         var_name[1]=0xA5;
 
         //                                fn new(ni2c: I2C, n_rst: GPIO, w: u8, h: u8, delay: DELAY, ngfx: AdafruitGFX) -> Self
         let mut delay =Delay::new(cp.SYST, HertzU32::MHz(216).raw());
-        let mydelay= DelayWrapper::new(delay);
+        let mut mydelay= DelayWrapper::new(delay);
         let mut rst: PB11<Output<PushPull>> = gpiob.pb11.into_push_pull_output();
-        rst.set_low();
+        // The controller only acknowledges once it is out of reset
+        rst.set_high();
+        mydelay.delay_ms(1);
         let gg: AdafruitGFX= AdafruitGFX::new(128, 32);
-        let mut display=AdafruitSSD1306::new(myi2c, rst, mydelay,gg);
+        let address = probe(&mut myi2c).unwrap_or(SSD1306_I2C_ADDRESS);
+        let mut display=AdafruitSSD1306::new(myi2c, address, OutputPinWrapper::new(rst), mydelay,gg, Controller::Ssd1306);
         display.clear_display();
-        display.begin(1);
+        display.begin(&OledConfig::for_panel(128, 32));
         display.splash();
         display.show();
         my_timer.set_duration(3000000.micros().into());
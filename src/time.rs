@@ -7,6 +7,7 @@
 
 use core::convert;
 
+use adafruit::clock::{ Clock, ClockInstant };
 use cortex_m_semihosting::hprintln;
 use fugit::{Duration, Instant};
 
@@ -79,3 +80,10 @@ impl Ticker {
         TickInstant::from_ticks(converted)
     }
 }
+
+// Lets the display driver measure idle time and animations with the RTC
+impl Clock for Ticker {
+    fn now(&mut self) -> ClockInstant {
+        Ticker::now(self)
+    }
+}